@@ -3,9 +3,9 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 use cw_deeplinks::msg::{
-    ExecuteMsg, InstantiateMsg, QueryMsg,
+    BatchResponse, CreateDeeplinksResponse, ExecuteMsg, InstallPackageResponse, InstantiateMsg, MigrateMsg, QueryMsg, TypeEnds,
 };
-use cw_deeplinks::query::{ConfigResponse, IdResponse};
+use cw_deeplinks::query::{ConfigResponse, DeeplinksResponse, DeeplinkWithValueResponse, DeletedIdsResponse, DumpStateResponse, ExportResponse, IsInstanceOfResponse, NameResponse, NamedDeeplinksResponse, NamesResponse, PackageIdsResponse, PackageResponse, PackageVersionsResponse, ValidateDeeplinksResponse, ValueAtPathResponse, ValueResponse};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -16,6 +16,25 @@ fn main() {
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
     export_schema(&schema_for!(ConfigResponse), &out_dir);
-    export_schema(&schema_for!(IdResponse), &out_dir);
+    export_schema(&schema_for!(NameResponse), &out_dir);
+    export_schema(&schema_for!(NamesResponse), &out_dir);
+    export_schema(&schema_for!(DeeplinksResponse), &out_dir);
+    export_schema(&schema_for!(IsInstanceOfResponse), &out_dir);
+    export_schema(&schema_for!(ValidateDeeplinksResponse), &out_dir);
+    export_schema(&schema_for!(TypeEnds), &out_dir);
+    export_schema(&schema_for!(NamedDeeplinksResponse), &out_dir);
+    export_schema(&schema_for!(DeletedIdsResponse), &out_dir);
+    export_schema(&schema_for!(DumpStateResponse), &out_dir);
+    export_schema(&schema_for!(PackageResponse), &out_dir);
+    export_schema(&schema_for!(PackageIdsResponse), &out_dir);
+    export_schema(&schema_for!(PackageVersionsResponse), &out_dir);
+    export_schema(&schema_for!(ExportResponse), &out_dir);
+    export_schema(&schema_for!(InstallPackageResponse), &out_dir);
+    export_schema(&schema_for!(BatchResponse), &out_dir);
+    export_schema(&schema_for!(CreateDeeplinksResponse), &out_dir);
+    export_schema(&schema_for!(ValueResponse), &out_dir);
+    export_schema(&schema_for!(DeeplinkWithValueResponse), &out_dir);
+    export_schema(&schema_for!(ValueAtPathResponse), &out_dir);
 }
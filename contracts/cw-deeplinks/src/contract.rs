@@ -1,27 +1,30 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, StdResult, MessageInfo, Reply, Api, Addr, Empty};
+use std::collections::BTreeMap;
+use cosmwasm_std::{to_json_binary, Binary, Deps, DepsMut, Env, StdError, StdResult, MessageInfo, Reply, Api, Addr, Order, Storage};
+use cw_storage_plus::Map;
 use cw2::{get_contract_version, set_contract_version};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{Config, CONFIG, DeeplinkState, DEEPLINKS, ID, NAMED_DEEPLINKS};
-use crate::execute::{CYBERLINK_ID_MSG, execute_create_deeplink, execute_cyberlink, execute_delete_deeplink, execute_update_deeplink, execute_update_admins, execute_update_executors, execute_create_deeplinks, execute_create_named_deeplink};
-use crate::query::{query_config, query_id, query_last_id, query_state};
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::state::{Config, CONFIG, CreationPolicy, DEFAULT_MAX_OBJECT_SIZE, DeeplinkState, DEEPLINK_NAMES, deeplinks, DEEPLINKS_KEY, ID, NAMED_DEEPLINKS, NAMED_DEEPLINKS_KEY, CORE_NAMES, core_names};
+use crate::execute::{CYBERLINK_ID_MSG, execute_create_deeplink, execute_cyberlink, execute_delete_deeplink, execute_update_deeplink, execute_update_admins, execute_update_executors, execute_create_deeplinks, execute_create_named_deeplink, execute_restore_deeplink, execute_purge_deeplinks, execute_update_creation_policy, execute_set_value, execute_update_value, execute_clear_value, execute_update_max_object_size, execute_create_package, execute_install_package, execute_activate_package_version, execute_uninstall_package, execute_set_type_ends, migrate_core_deeplinks, execute_rename_deeplink, execute_add_alias, execute_remove_alias, execute_set_canonical_name, execute_batch};
+use crate::query::{query_by_creator, query_by_from, query_by_from_to, query_by_to, query_by_type, query_by_type_from, query_by_type_to, query_config, query_deeplink_with_value, query_dump_state, query_export_package, query_export_subgraph, query_id, query_instances_of, query_is_instance_of, query_last_id, query_list_deeplinks, query_list_deleted, query_list_named, query_names, query_package, query_package_ids, query_package_versions, query_resolve_name, query_type_ends, query_validate_deeplinks, query_value, query_value_at_path};
 
 use cyber_std::CyberMsgWrapper;
 use semver::Version;
+use serde::{Deserialize, Serialize};
 
 type Response = cosmwasm_std::Response<CyberMsgWrapper>;
 
 const CONTRACT_NAME: &str = "cw-deeplinks";
-const CONTRACT_VERSION: &str = "0.0.1";
+const CONTRACT_VERSION: &str = "0.0.5";
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
+    env: Env,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
@@ -29,42 +32,42 @@ pub fn instantiate(
     let config = Config {
         admins: map_validate(deps.api, &msg.admins)?,
         executors: map_validate(deps.api, &msg.executers)?,
+        creation_policy: CreationPolicy::Open,
+        max_object_size: DEFAULT_MAX_OBJECT_SIZE,
     };
     CONFIG.save(deps.storage, &config)?;
 
-    ID.save(deps.storage, &0)?;
-
-    let id = ID.load(deps.storage)? + 1;
-    ID.save(deps.storage, &id)?;
-    DEEPLINKS.save(deps.storage, id, &DeeplinkState {
-        type_: "Type".to_string(),
-        from: "Any".to_string(),
-        to: "Any".to_string(),
-    })?;
-    NAMED_DEEPLINKS.save(deps.storage,
-     "Type", &DeeplinkState {
-        type_: "Type".to_string(),
-        from: "Any".to_string(),
-        to: "Any".to_string(),
-    })?;
-
-    let id = ID.load(deps.storage)? + 1;
-    ID.save(deps.storage, &id)?;
-    DEEPLINKS.save(deps.storage, id, &DeeplinkState {
-        type_: "Any".to_string(),
-        from: "Null".to_string(),
-        to: "Null".to_string(),
-    })?;
-    NAMED_DEEPLINKS.save(deps.storage,
-     "Any", &DeeplinkState {
-        type_: "Type".to_string(),
-        from: "Null".to_string(),
-        to: "Null".to_string(),
-    })?;
+    // Type and Any reference each other, so their ids are assigned upfront.
+    // As in core.json, Type goes from Any to Any and Any has no ends
+    let (type_id, any_id) = (1, 2);
+    let core_state = DeeplinkState {
+        type_: type_id,
+        from: Some(any_id),
+        to: Some(any_id),
+        creator: info.sender,
+        created_height: env.block.height,
+        created_at: env.block.time,
+    };
+    let any_state = DeeplinkState { from: None, to: None, ..core_state.clone() };
+    for (id, name, state) in [(type_id, "Type", core_state), (any_id, "Any", any_state)] {
+        deeplinks().save(deps.storage, id, &state)?;
+        NAMED_DEEPLINKS.save(deps.storage, name, &id)?;
+        DEEPLINK_NAMES.save(deps.storage, id, &name.to_string())?;
+    }
+    ID.save(deps.storage, &any_id)?;
+    save_core_names(deps.storage)?;
 
     Ok(Response::default())
 }
 
+/// Records the names of core.json, so they aren't parsed on every check
+fn save_core_names(storage: &mut dyn Storage) -> StdResult<()> {
+    for name in core_names()? {
+        CORE_NAMES.save(storage, name.as_str(), &true)?;
+    }
+    Ok(())
+}
+
 pub fn map_validate(api: &dyn Api, admins: &[String]) -> StdResult<Vec<Addr>> {
     admins.iter().map(|addr| api.addr_validate(addr)).collect()
 }
@@ -77,13 +80,30 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::CreatedNamedDeeplink { name, deeplink } => execute_create_named_deeplink(deps, env, info, name, deeplink),
-        ExecuteMsg::CreateDeeplink { deeplink } => execute_create_deeplink(deps, env, info, deeplink),
-        ExecuteMsg::CreateDeeplinks { deeplinks } => execute_create_deeplinks(deps, env, info, deeplinks),
-        ExecuteMsg::UpdateDeeplink { type_, from, to } => execute_update_deeplink(deps, env, info, type_, from, to),
-        ExecuteMsg::DeleteDeeplink { id } => execute_delete_deeplink(deps, env, info, id),
+        ExecuteMsg::CreatedNamedDeeplink { name, deeplink, package, upsert } => execute_create_named_deeplink(deps, env, info, name, deeplink, package, upsert),
+        ExecuteMsg::CreateDeeplink { deeplink, package } => execute_create_deeplink(deps, env, info, deeplink, package),
+        ExecuteMsg::CreateDeeplinks { deeplinks, package, continue_on_error } => execute_create_deeplinks(deps, env, info, deeplinks, package, continue_on_error),
+        ExecuteMsg::Batch { ops, package } => execute_batch(deps, env, info, ops, package),
+        ExecuteMsg::UpdateDeeplink { id, deeplink, package } => execute_update_deeplink(deps, env, info, id, deeplink, package),
+        ExecuteMsg::RenameDeeplink { name, new_name } => execute_rename_deeplink(deps, env, info, name, new_name),
+        ExecuteMsg::AddAlias { id, alias } => execute_add_alias(deps, env, info, id, alias),
+        ExecuteMsg::RemoveAlias { alias } => execute_remove_alias(deps, env, info, alias),
+        ExecuteMsg::SetCanonicalName { name } => execute_set_canonical_name(deps, env, info, name),
+        ExecuteMsg::SetTypeEnds { type_, ends } => execute_set_type_ends(deps, env, info, type_, ends),
+        ExecuteMsg::CreatePackage { name } => execute_create_package(deps, env, info, name),
+        ExecuteMsg::InstallPackage { package, version, deeplinks, dependencies } => execute_install_package(deps, env, info, package, version, deeplinks, dependencies),
+        ExecuteMsg::UninstallPackage { package, mode } => execute_uninstall_package(deps, env, info, package, mode),
+        ExecuteMsg::ActivatePackageVersion { package, version } => execute_activate_package_version(deps, env, info, package, version),
+        ExecuteMsg::DeleteDeeplink { id, mode } => execute_delete_deeplink(deps, env, info, id, mode),
+        ExecuteMsg::RestoreDeeplink { id } => execute_restore_deeplink(deps, env, info, id),
+        ExecuteMsg::PurgeDeeplinks { limit } => execute_purge_deeplinks(deps, env, info, limit),
+        ExecuteMsg::SetValue { id, value } => execute_set_value(deps, env, info, id, value),
+        ExecuteMsg::UpdateValue { id, value } => execute_update_value(deps, env, info, id, value),
+        ExecuteMsg::ClearValue { id } => execute_clear_value(deps, env, info, id),
         ExecuteMsg::UpdateAdmins { new_admins } => execute_update_admins(deps, env, info, new_admins),
         ExecuteMsg::UpdateExecutors { new_executors } => execute_update_executors(deps, env, info, new_executors),
+        ExecuteMsg::UpdateCreationPolicy { policy } => execute_update_creation_policy(deps, env, info, policy),
+        ExecuteMsg::UpdateMaxObjectSize { max_object_size } => execute_update_max_object_size(deps, env, info, max_object_size),
         ExecuteMsg::Cyberlink { links } => execute_cyberlink(deps, env, info, links),
     }
 }
@@ -91,10 +111,34 @@ pub fn execute(
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::LastId {} => to_binary(&query_last_id(deps)?),
-        QueryMsg::DebugState {} => to_binary(&query_state(deps)?),
-        QueryMsg::Config {} => to_binary(&query_config(deps)?),
-        QueryMsg::Deeplink { id } => to_binary(&query_id(deps, id)?),
+        QueryMsg::LastId {} => to_json_binary(&query_last_id(deps)?),
+        QueryMsg::ListDeeplinks { start_after, limit } => to_json_binary(&query_list_deeplinks(deps, start_after, limit)?),
+        QueryMsg::ListNamed { start_after, limit } => to_json_binary(&query_list_named(deps, start_after, limit)?),
+        QueryMsg::ListDeleted { start_after, limit } => to_json_binary(&query_list_deleted(deps, start_after, limit)?),
+        QueryMsg::DumpState { start_after, limit } => to_json_binary(&query_dump_state(deps, start_after, limit)?),
+        QueryMsg::Value { id } => to_json_binary(&query_value(deps, id)?),
+        QueryMsg::DeeplinkWithValue { id } => to_json_binary(&query_deeplink_with_value(deps, id)?),
+        QueryMsg::ValueAtPath { id, path } => to_json_binary(&query_value_at_path(deps, id, path)?),
+        QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::Deeplink { id } => to_json_binary(&query_id(deps, id)?),
+        QueryMsg::Package { name } => to_json_binary(&query_package(deps, name)?),
+        QueryMsg::ExportPackage { package, limit } => to_json_binary(&query_export_package(deps, package, limit)?),
+        QueryMsg::ExportSubgraph { root, limit } => to_json_binary(&query_export_subgraph(deps, root, limit)?),
+        QueryMsg::PackageVersions { package } => to_json_binary(&query_package_versions(deps, package)?),
+        QueryMsg::PackageIds { package, start_after, limit } => to_json_binary(&query_package_ids(deps, package, start_after, limit)?),
+        QueryMsg::ResolveName { name } => to_json_binary(&query_resolve_name(deps, name)?),
+        QueryMsg::Names { id } => to_json_binary(&query_names(deps, id)?),
+        QueryMsg::ByType { type_, start_after, limit } => to_json_binary(&query_by_type(deps, type_, start_after, limit)?),
+        QueryMsg::ByFrom { from, start_after, limit } => to_json_binary(&query_by_from(deps, from, start_after, limit)?),
+        QueryMsg::ByTo { to, start_after, limit } => to_json_binary(&query_by_to(deps, to, start_after, limit)?),
+        QueryMsg::ByTypeFrom { type_, from, start_after, limit } => to_json_binary(&query_by_type_from(deps, type_, from, start_after, limit)?),
+        QueryMsg::ByTypeTo { type_, to, start_after, limit } => to_json_binary(&query_by_type_to(deps, type_, to, start_after, limit)?),
+        QueryMsg::ByFromTo { from, to, start_after, limit } => to_json_binary(&query_by_from_to(deps, from, to, start_after, limit)?),
+        QueryMsg::ByCreator { creator, start_after, limit } => to_json_binary(&query_by_creator(deps, creator, start_after, limit)?),
+        QueryMsg::TypeEnds { type_ } => to_json_binary(&query_type_ends(deps, type_)?),
+        QueryMsg::IsInstanceOf { id, type_ } => to_json_binary(&query_is_instance_of(deps, id, type_)?),
+        QueryMsg::ValidateDeeplinks { deeplinks, package, sender } => to_json_binary(&query_validate_deeplinks(deps, deeplinks, package, sender)?),
+        QueryMsg::InstancesOf { type_, include_subtypes, start_after, limit } => to_json_binary(&query_instances_of(deps, type_, include_subtypes, start_after, limit)?),
     }
 }
 
@@ -109,8 +153,8 @@ pub fn reply(_deps: DepsMut, _env: Env, reply: Reply) -> Result<Response, Contra
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(
     deps: DepsMut,
-    _env: Env,
-    _msg: Empty,
+    env: Env,
+    msg: MigrateMsg,
 ) -> Result<Response, ContractError> {
     let stored = get_contract_version(deps.storage)?;
     if stored.contract != CONTRACT_NAME {
@@ -129,8 +173,178 @@ pub fn migrate(
     }
 
     if storage_version < version {
+        if storage_version < Version::new(0, 0, 2) {
+            let names = match_legacy_names(deps.storage)?;
+            resolve_legacy_deeplinks(deps.storage, &names)?;
+            rebuild_named_deeplinks(deps.storage, &names)?;
+        }
+        if storage_version < Version::new(0, 0, 4) {
+            rebuild_deeplinks(deps.storage, &env)?;
+        }
+        if storage_version < Version::new(0, 0, 5) {
+            clear_substituted_ends(deps.storage)?;
+        }
         set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     }
 
-    Ok(Response::new())
+    save_core_names(deps.storage)?;
+    let core_ids = migrate_core_deeplinks(deps, &env, msg.core_deeplinks)?;
+
+    Ok(Response::new()
+        .add_attribute("core_ids", core_ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(",")))
+}
+
+/// Deeplink as stored by 0.0.1, in the deeplinks map and copied under its name.
+/// Other deeplinks are referenced by name, missing ends are Any
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+struct LegacyNamedState {
+    #[serde(rename = "type")]
+    type_: String,
+    from: String,
+    to: String,
+}
+
+impl LegacyNamedState {
+    fn resolve(self, id: u64, lookup: &dyn Fn(&str) -> StdResult<Option<u64>>) -> StdResult<LegacyDeeplinkState> {
+        let type_ = lookup(&self.type_)?.ok_or_else(|| {
+            StdError::generic_err(format!("Unknown type {} of legacy deeplink {}", self.type_, id))
+        })?;
+        Ok(LegacyDeeplinkState { type_, from: lookup(&self.from)?, to: lookup(&self.to)? })
+    }
+}
+
+/// Pairs the 0.0.1 names with ids by their copied state. Re-creating a name pointed it to
+/// the newer deeplink, so equal states are paired from the last id and the last core.json
+/// name backwards and older copies stay unnamed. Instantiate named Type and Any with states
+/// of their own, without a copy they keep ids 1 and 2.
+fn match_legacy_names(storage: &dyn Storage) -> StdResult<BTreeMap<String, u64>> {
+    let legacy_named: Map<&str, LegacyNamedState> = Map::new(NAMED_DEEPLINKS_KEY);
+    let legacy_deeplinks: Map<u64, LegacyNamedState> = Map::new(DEEPLINKS_KEY);
+
+    let mut ids_by_state: BTreeMap<LegacyNamedState, Vec<u64>> = BTreeMap::new();
+    for item in legacy_deeplinks.range(storage, None, None, Order::Ascending) {
+        let (id, state) = item?;
+        ids_by_state.entry(state).or_default().push(id);
+    }
+    let mut names_by_state: BTreeMap<LegacyNamedState, Vec<String>> = BTreeMap::new();
+    for item in legacy_named.range(storage, None, None, Order::Ascending) {
+        let (name, state) = item?;
+        names_by_state.entry(state).or_default().push(name);
+    }
+
+    let core = core_names()?;
+    let position = |name: &str| core.iter().position(|core| core == name).unwrap_or(core.len());
+    let mut names = BTreeMap::new();
+    let mut unmatched = vec![];
+    for (state, mut state_names) in names_by_state {
+        state_names.sort_by_key(|name| (position(name), name.clone()));
+        let ids = ids_by_state.remove(&state).unwrap_or_default();
+        let matched = state_names.len().min(ids.len());
+        unmatched.extend(state_names.drain(..state_names.len() - matched));
+        names.extend(state_names.into_iter().zip(ids[ids.len() - matched..].iter().copied()));
+    }
+    for (name, id) in [("Type", 1), ("Any", 2)] {
+        if unmatched.iter().any(|unmatched| unmatched == name) && !names.values().any(|named| *named == id) {
+            names.insert(name.to_string(), id);
+        }
+    }
+    Ok(names)
+}
+
+/// Before 0.0.2 deeplinks referenced their type and ends by name, ends that name
+/// no deeplink (Null of the instantiated Any) are dropped
+fn resolve_legacy_deeplinks(storage: &mut dyn Storage, names: &BTreeMap<String, u64>) -> StdResult<()> {
+    let legacy_deeplinks: Map<u64, LegacyNamedState> = Map::new(DEEPLINKS_KEY);
+    let resolved_deeplinks: Map<u64, LegacyDeeplinkState> = Map::new(DEEPLINKS_KEY);
+    let all = legacy_deeplinks
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<(u64, LegacyNamedState)>>>()?;
+    for (id, legacy) in all {
+        let resolved = legacy.resolve(id, &|name| Ok(names.get(name).copied()))?;
+        resolved_deeplinks.save(storage, id, &resolved)?;
+    }
+
+    Ok(())
+}
+
+/// Before 0.0.2 named deeplinks were stored as copies of the deeplink state,
+/// replace them with name -> id pointers and the id -> name records of the matched names
+fn rebuild_named_deeplinks(storage: &mut dyn Storage, names: &BTreeMap<String, u64>) -> StdResult<()> {
+    // Raw keys, the legacy values do not deserialize as ids
+    let legacy_names = NAMED_DEEPLINKS
+        .keys_raw(storage, None, None, Order::Ascending)
+        .map(String::from_utf8)
+        .collect::<Result<Vec<String>, _>>()?;
+    for name in legacy_names {
+        NAMED_DEEPLINKS.remove(storage, name.as_str());
+    }
+
+    for (name, id) in names {
+        NAMED_DEEPLINKS.save(storage, name.as_str(), id)?;
+        DEEPLINK_NAMES.save(storage, *id, name)?;
+    }
+
+    Ok(())
+}
+
+/// Deeplink of 0.0.2 and 0.0.3
+#[derive(Serialize, Deserialize)]
+struct LegacyDeeplinkState {
+    #[serde(rename = "type")]
+    type_: u64,
+    from: Option<u64>,
+    to: Option<u64>,
+}
+
+/// Deeplink before 0.0.4 in either of its layouts, values left by 0.0.1 still
+/// reference other deeplinks by name
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum LegacyDeeplink {
+    Ids(LegacyDeeplinkState),
+    Names(LegacyNamedState),
+}
+
+/// Before 0.0.3 deeplinks were stored in a plain map without indexes,
+/// before 0.0.4 they had no creator. Deeplinks of unknown creator are
+/// attributed to the contract itself at the migration block.
+fn rebuild_deeplinks(storage: &mut dyn Storage, env: &Env) -> StdResult<()> {
+    let legacy_deeplinks: Map<u64, LegacyDeeplink> = Map::new(DEEPLINKS_KEY);
+    let all = legacy_deeplinks
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<(u64, LegacyDeeplink)>>>()?;
+    for (id, legacy) in all {
+        let legacy = match legacy {
+            LegacyDeeplink::Ids(legacy) => legacy,
+            LegacyDeeplink::Names(legacy) => legacy.resolve(id, &|name| NAMED_DEEPLINKS.may_load(storage, name))?,
+        };
+        let deeplink = DeeplinkState {
+            type_: legacy.type_,
+            from: legacy.from,
+            to: legacy.to,
+            creator: env.contract.address.clone(),
+            created_height: env.block.height,
+            created_at: env.block.time,
+        };
+        // Existing type, from and to index entries keep their keys, nothing to remove
+        deeplinks().replace(storage, id, Some(&deeplink), None)?;
+    }
+
+    Ok(())
+}
+
+/// Before 0.0.5 missing ends were stored as Any. Types keep Any as their
+/// constraint, other deeplinks with both ends Any get no ends.
+fn clear_substituted_ends(storage: &mut dyn Storage) -> StdResult<()> {
+    let (type_id, any_id) = (NAMED_DEEPLINKS.load(storage, "Type")?, NAMED_DEEPLINKS.load(storage, "Any")?);
+    let substituted = deeplinks().idx.from
+        .prefix(any_id)
+        .range(storage, None, None, Order::Ascending)
+        .filter(|item| !matches!(item, Ok((_, state)) if state.to != Some(any_id) || state.type_ == type_id))
+        .collect::<StdResult<Vec<_>>>()?;
+    for (id, state) in substituted {
+        deeplinks().save(storage, id, &DeeplinkState { from: None, to: None, ..state })?;
+    }
+
+    Ok(())
 }
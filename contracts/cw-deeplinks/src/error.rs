@@ -6,6 +6,78 @@ pub enum ContractError {
     #[error("Deleted Deeplink: {id}")]
     DeletedDeeplink { id: Uint64 },
 
+    #[error("Deeplink not exists: {id}")]
+    DeeplinkNotExists { id: Uint64 },
+
+    #[error("Deeplink is not deleted: {id}")]
+    DeeplinkNotDeleted { id: Uint64 },
+
+    #[error("Deeplink {id} is referenced by: {referrers:?}")]
+    DeeplinkHasReferrers { id: Uint64, referrers: Vec<Uint64> },
+
+    #[error("Deeplink already has a value: {id}")]
+    ValueAlreadySet { id: Uint64 },
+
+    #[error("Deeplink has no value: {id}")]
+    ValueNotSet { id: Uint64 },
+
+    #[error("Type {type_} of deeplink {id} declares no value")]
+    ValueNotDeclared { id: Uint64, type_: String },
+
+    #[error("Deeplink {id} of type {type_} takes {expected} values, received: {received}")]
+    ValueKindMismatch { id: Uint64, type_: String, expected: String, received: String },
+
+    #[error("Object value of deeplink {id} is {size} bytes, max: {max}")]
+    ObjectTooLarge { id: Uint64, size: u64, max: u32 },
+
+    #[error("Invalid JSON object: {reason}")]
+    InvalidObject { reason: String },
+
+    #[error("Invalid name: {name}")]
+    InvalidName { name: String },
+
+    #[error("Name already exists: {name}")]
+    AlreadyExists { name: String },
+
+    #[error("Name not exists: {name}")]
+    NameNotExists { name: String },
+
+    #[error("Name is not an alias: {name}")]
+    NotAlias { name: String },
+
+    #[error("Unknown placeholder: {placeholder}")]
+    UnknownPlaceholder { placeholder: String },
+
+    #[error("Invalid placeholder: {placeholder}")]
+    InvalidPlaceholder { placeholder: String },
+
+    #[error("Core name can be changed only by migration: {name}")]
+    CoreName { name: String },
+
+    #[error("Package not exists: {package}")]
+    PackageNotExists { package: String },
+
+    #[error("Package already exists: {package}")]
+    PackageAlreadyExists { package: String },
+
+    #[error("Package {package} is required by: {dependents:?}")]
+    PackageHasDependents { package: String, dependents: Vec<String> },
+
+    #[error("Package {package} is referenced by: {referrers:?}")]
+    PackageHasReferrers { package: String, referrers: Vec<Uint64> },
+
+    #[error("Package {package} already has version {version}")]
+    PackageVersionExists { package: String, version: String },
+
+    #[error("Package {package} has no version {version}")]
+    PackageVersionNotExists { package: String, version: String },
+
+    #[error("Missing dependency: {package} {required}")]
+    DependencyMissing { package: String, required: String },
+
+    #[error("Package {package} {version} doesn't match required {required}")]
+    DependencyIncompatible { package: String, required: String, version: String },
+
     #[error("Particular links is not allowed id: {id}, from: {from}, to: {to}, type: {type_}")]
     InvalidDeeplink {id: Uint64, from: String, to: String, type_: String},
 
@@ -18,15 +90,20 @@ pub enum ContractError {
     #[error("To not exists: {to}")]
     ToNotExists { to: String },
 
-    #[error("Type conflict \
-        link ( id: {id}, type: {type_}, from: {from}, to: {to} ),\
-        expected type: ( type: {expected_type}, from: {expected_from}, to: {expected_to} ),\
-        received_from: ( type: {received_type}, from: {received_from}, to: {received_to} )")]
-    TypeConflict {
-        id: String, type_: String, from: String, to: String,
-        expected_type: String, expected_from: String, expected_to: String,
-        received_type: String, received_from: String, received_to: String
-    },
+    #[error("Type conflict {conflict}")]
+    TypeConflict { conflict: Box<TypeConflict> },
+
+    #[error("Type {type_} requires the {end} end")]
+    EndRequired { type_: String, end: String },
+
+    #[error("Type {type_} forbids the {end} end")]
+    EndForbidden { type_: String, end: String },
+
+    #[error("Type {type_} can't extend itself or its subtype")]
+    TypeCycle { type_: String },
+
+    #[error("Entry {index}: {error}")]
+    BatchEntry { index: u32, error: Box<ContractError> },
 
     #[error("{0}")]
     Std(#[from] StdError),
@@ -52,3 +129,15 @@ impl From<semver::Error> for ContractError {
         Self::SemVer(err.to_string())
     }
 }
+
+/// Deeplink and the ends its type expects, boxed in the error to keep it small
+#[derive(Error, Debug, PartialEq)]
+#[error("\
+    link ( id: {id}, type: {type_}, from: {from}, to: {to} ),\
+    expected type: ( type: {expected_type}, from: {expected_from}, to: {expected_to} ),\
+    received_from: ( type: {received_type}, from: {received_from}, to: {received_to} )")]
+pub struct TypeConflict {
+    pub id: String, pub type_: String, pub from: String, pub to: String,
+    pub expected_type: String, pub expected_from: String, pub expected_to: String,
+    pub received_type: String, pub received_from: String, pub received_to: String
+}
@@ -1,232 +1,1761 @@
-use cosmwasm_std::{attr, Deps, DepsMut, Env, MessageInfo, StdResult, SubMsg, Uint64};
-use cosmwasm_std::Order::Ascending;
-use crate::error::ContractError;
-use crate::state::{CONFIG, DeeplinkState, DEEPLINKS, ID, DELETED_IDS, NAMED_DEEPLINKS};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use cosmwasm_std::{attr, to_json_binary, Addr, Attribute, Deps, DepsMut, Env, MessageInfo, Order, StdError, StdResult, Storage, SubMsg, Uint64};
+use crate::error::{ContractError, TypeConflict};
+use crate::state::{CONFIG, Config, CreationPolicy, TypeAllowList, DeeplinkState, deeplinks, ID, DELETED_IDS, NAMED_DEEPLINKS, DEEPLINK_NAMES, NUMBERS_VALUES, STRINGS_VALUES, OBJECTS_VALUES, PACKAGES, PackageState, PACKAGE_IDS, DEEPLINK_PACKAGES, PackageEntry, PACKAGE_VERSIONS, PackageVersionState, PACKAGE_DEPENDENTS, TYPE_ENDS, DEEPLINK_ALIASES, CORE_NAMES, Parents, is_core_name, is_local_name, is_subtype, names_of, lookup_name, split_name, type_parents};
 use cyber_std::{create_cyberlink_msg, Link, CyberMsgWrapper};
 use crate::contract::map_validate;
-use crate::msg::Deeplink;
+use crate::msg::{BatchOp, BatchResponse, CreateDeeplinksResponse, CreatedDeeplink, CreationPolicyMsg, EntryFailure, Deeplink, DeeplinkRef, DeleteMode, EndRule, InstallPackageResponse, ManifestValueContent, NamedDeeplink, PackageDependency, TypeEnds, Value};
+use semver::{Version, VersionReq};
+use crate::query::{load_value, Diagnostic};
+use crate::json::validate_object;
 
 type Response = cosmwasm_std::Response<CyberMsgWrapper>;
 pub const CYBERLINK_ID_MSG: u64 = 42;
 
+const DEFAULT_PURGE_LIMIT: u32 = 10;
+const MAX_PURGE_LIMIT: u32 = 100;
+
+fn resolve_deeplink(
+    deps: Deps,
+    deeplink_ref: &DeeplinkRef,
+    package: Option<&str>,
+) -> Result<Option<(u64, DeeplinkState)>, ContractError> {
+    let id = match deeplink_ref {
+        DeeplinkRef::Id(id) => id.u64(),
+        DeeplinkRef::Name(name) => match lookup_name(deps.storage, name, package)? {
+            Some(id) => id,
+            None => return Ok(None),
+        },
+    };
+    if DELETED_IDS.has(deps.storage, id) {
+        return Err(ContractError::DeletedDeeplink { id: Uint64::new(id) });
+    }
+    Ok(deeplinks().may_load(deps.storage, id)?.map(|state| (id, state)))
+}
+
+/// Live deeplinks using the id as their type, from or to
+fn referrers(deps: Deps, id: u64) -> StdResult<BTreeSet<u64>> {
+    let idx = deeplinks().idx;
+    let mut referrers = BTreeSet::new();
+    for prefix in [idx.type_.prefix(id), idx.from.prefix(id), idx.to.prefix(id)] {
+        for referrer in prefix.keys(deps.storage, None, None, Order::Ascending) {
+            let referrer = referrer?;
+            if referrer != id && !DELETED_IDS.has(deps.storage, referrer) {
+                referrers.insert(referrer);
+            }
+        }
+    }
+    Ok(referrers)
+}
+
+fn delete_deeplink(
+    deps: DepsMut,
+    sender: &Addr,
+    id: u64,
+    mode: DeleteMode,
+) -> Result<BTreeSet<u64>, ContractError> {
+    if !deeplinks().has(deps.storage, id) {
+        return Err(ContractError::DeeplinkNotExists { id: Uint64::new(id) });
+    }
+    if DELETED_IDS.has(deps.storage, id) {
+        return Err(ContractError::DeletedDeeplink { id: Uint64::new(id) });
+    }
+
+    // Referrers are only reported to senders allowed to delete the deeplink
+    let cfg = CONFIG.load(deps.storage)?;
+    if !cfg.can_modify_deeplink(sender.as_ref(), &deeplinks().load(deps.storage, id)?) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut affected = BTreeSet::from([id]);
+    match mode {
+        DeleteMode::Restrict => {
+            let referrers = referrers(deps.as_ref(), id)?;
+            if !referrers.is_empty() {
+                return Err(ContractError::DeeplinkHasReferrers {
+                    id: Uint64::new(id),
+                    referrers: referrers.into_iter().map(Uint64::new).collect(),
+                });
+            }
+        }
+        DeleteMode::Cascade => {
+            let mut queue = vec![id];
+            while let Some(next) = queue.pop() {
+                for referrer in referrers(deps.as_ref(), next)? {
+                    if affected.insert(referrer) {
+                        queue.push(referrer);
+                    }
+                }
+            }
+        }
+    }
+
+    // Cascade needs the permission for every dependent too
+    for id in affected.iter() {
+        if !cfg.can_modify_deeplink(sender.as_ref(), &deeplinks().load(deps.storage, *id)?) {
+            return Err(ContractError::Unauthorized {});
+        }
+        check_not_core(deps.storage, *id)?;
+    }
+
+    // Mark the deeplinks as deleted
+    for id in affected.iter() {
+        DELETED_IDS.save(deps.storage, *id, &true)?;
+    }
+
+    Ok(affected)
+}
+
+/// Attributes `id`, `type`, `from` and `to` of a written deeplink, repeated per deeplink
+/// so callers can read the ids from submessage events
+fn deeplink_attributes(deps: Deps, id: u64) -> StdResult<Vec<Attribute>> {
+    let state = deeplinks().load(deps.storage, id)?;
+    let end = |end: Option<u64>| end.map_or_else(|| "_".to_string(), |end| end.to_string());
+    Ok(vec![
+        attr("id", id.to_string()),
+        attr("type", state.type_.to_string()),
+        attr("from", end(state.from)),
+        attr("to", end(state.to)),
+    ])
+}
+
+/// Deeplink with type and ends resolved to ids
+struct ValidDeeplink {
+    type_: u64,
+    from: Option<u64>,
+    to: Option<u64>,
+}
+
+impl ValidDeeplink {
+    fn into_state(self, creator: Addr, env: &Env) -> DeeplinkState {
+        DeeplinkState {
+            type_: self.type_,
+            from: self.from,
+            to: self.to,
+            creator,
+            created_height: env.block.height,
+            created_at: env.block.time,
+        }
+    }
+}
+
 fn validate_deeplink(
     deps: Deps,
     id: Option<String>,
-    deeplink: Deeplink
-) -> Result<(), ContractError> {
-    // Validation
-    if deeplink.from != deeplink.to && (deeplink.from.is_none() || deeplink.to.is_none()) {
-        return Err(ContractError::InvalidDeeplink {
-            id: Uint64::zero(),
-            from: deeplink.from.unwrap_or_else(|| "_".to_string()),
-            to: deeplink.to.unwrap_or_else(|| "_".to_string()),
-            type_: deeplink.type_.clone(),
+    deeplink: Deeplink,
+    package: Option<&str>,
+) -> Result<ValidDeeplink, ContractError> {
+    let resolve = |deeplink_ref: &DeeplinkRef| resolve_deeplink(deps, deeplink_ref, package);
+    let parents = |type_: u64| type_parents(deps.storage, type_);
+    validate_deeplink_with(deps, id, deeplink, &resolve, &parents)
+}
+
+type Resolver<'a> = dyn Fn(&DeeplinkRef) -> Result<Option<(u64, DeeplinkState)>, ContractError> + 'a;
+
+/// Deeplink with type and ends resolved as far as they exist, and every problem found on the way
+struct CheckedDeeplink {
+    type_: Option<(u64, DeeplinkState)>,
+    from: Option<(u64, DeeplinkState)>,
+    to: Option<(u64, DeeplinkState)>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Collects everything that makes the deeplink invalid instead of stopping at the first problem
+fn check_deeplink(
+    deps: Deps,
+    deeplink: &Deeplink,
+    resolve: &Resolver,
+    parents: &Parents,
+) -> Result<CheckedDeeplink, ContractError> {
+    let any = NAMED_DEEPLINKS.load(deps.storage, "Any")?;
+    let mut diagnostics = vec![];
+
+    let mut resolve_end = |end: &str, deeplink_ref: &DeeplinkRef| -> Result<Option<(u64, DeeplinkState)>, ContractError> {
+        match resolve(deeplink_ref) {
+            Ok(None) => {
+                diagnostics.push(Diagnostic::NotExists { end: end.to_string(), reference: deeplink_ref.to_string() });
+                Ok(None)
+            }
+            Err(ContractError::DeletedDeeplink { id }) => {
+                diagnostics.push(Diagnostic::Deleted { end: end.to_string(), id });
+                Ok(None)
+            }
+            resolved => resolved,
+        }
+    };
+    let dtype_ = resolve_end("type", &deeplink.type_)?;
+    let dfrom = deeplink.from.as_ref().map(|from| resolve_end("from", from)).transpose()?.flatten();
+    let dto = deeplink.to.as_ref().map(|to| resolve_end("to", to)).transpose()?.flatten();
+
+    let (type_id, type_state) = match &dtype_ {
+        Some(resolved) => resolved,
+        None => return Ok(CheckedDeeplink { type_: dtype_, from: dfrom, to: dto, diagnostics }),
+    };
+
+    // Each end must be present or absent as the type declares
+    let ends = TYPE_ENDS.may_load(deps.storage, *type_id)?.unwrap_or_default();
+    for (end, rule, present) in [("from", ends.from, deeplink.from.is_some()), ("to", ends.to, deeplink.to.is_some())] {
+        match (rule, present) {
+            (EndRule::Required, false) => diagnostics.push(Diagnostic::EndRequired { end: end.to_string() }),
+            (EndRule::Forbidden, true) => diagnostics.push(Diagnostic::EndForbidden { end: end.to_string() }),
+            _ => {}
+        }
+    }
+
+    // Subtypes match the constraints of their parents.
+    // A type without an end or with Any accepts any deeplink there
+    for (end, expected, received) in [("from", type_state.from, &dfrom), ("to", type_state.to, &dto)] {
+        if let (Some(expected), Some((_, state))) = (expected, received) {
+            if expected != any && !is_subtype(state.type_, expected, parents)? {
+                diagnostics.push(Diagnostic::TypeMismatch {
+                    end: end.to_string(),
+                    expected: Uint64::new(expected),
+                    received: Uint64::new(state.type_),
+                });
+            }
+        }
+    }
+
+    // A type can't extend itself or one of its subtypes
+    if let (Some((from_id, _)), Some((to_id, _))) = (&dfrom, &dto) {
+        if Some(*type_id) == NAMED_DEEPLINKS.may_load(deps.storage, "Extends")? && is_subtype(*to_id, *from_id, parents)? {
+            diagnostics.push(Diagnostic::TypeCycle {});
+        }
+    }
+
+    Ok(CheckedDeeplink { type_: dtype_, from: dfrom, to: dto, diagnostics })
+}
+
+/// Validates against deeplinks given by the resolver, which can see links not written yet.
+/// The first problem found is returned as the error
+fn validate_deeplink_with(
+    deps: Deps,
+    id: Option<String>,
+    deeplink: Deeplink,
+    resolve: &Resolver,
+    parents: &Parents,
+) -> Result<ValidDeeplink, ContractError> {
+    let checked = check_deeplink(deps, &deeplink, resolve, parents)?;
+    let reference = |end: &Option<DeeplinkRef>| end.as_ref().map_or_else(|| "_".to_string(), |end| end.to_string());
+
+    if let Some(diagnostic) = checked.diagnostics.into_iter().next() {
+        return Err(match diagnostic {
+            Diagnostic::NotExists { end, reference } => match end.as_str() {
+                "type" => ContractError::TypeNotExists { type_: reference },
+                "from" => ContractError::FromNotExists { from: reference },
+                _ => ContractError::ToNotExists { to: reference },
+            },
+            Diagnostic::Deleted { id, .. } => ContractError::DeletedDeeplink { id },
+            Diagnostic::EndRequired { end } => ContractError::EndRequired { type_: deeplink.type_.to_string(), end },
+            Diagnostic::EndForbidden { end } => ContractError::EndForbidden { type_: deeplink.type_.to_string(), end },
+            Diagnostic::TypeMismatch { .. } => {
+                let display = |end: Option<u64>| end.map_or_else(|| "_".to_string(), |end| end.to_string());
+                let received = |end: &Option<(u64, DeeplinkState)>| end.as_ref().map_or_else(|| "_".to_string(), |(_, state)| state.type_.to_string());
+                let (type_id, type_state) = checked.type_.as_ref().map_or((0, None), |(type_id, state)| (*type_id, Some(state)));
+                ContractError::TypeConflict { conflict: Box::new(TypeConflict {
+                    id: id.unwrap_or_else(|| "_".to_string()),
+                    type_: deeplink.type_.to_string(),
+                    from: reference(&deeplink.from),
+                    to: reference(&deeplink.to),
+                    expected_type: type_id.to_string(),
+                    expected_from: display(type_state.and_then(|state| state.from)),
+                    expected_to: display(type_state.and_then(|state| state.to)),
+                    received_type: type_id.to_string(),
+                    received_from: received(&checked.from),
+                    received_to: received(&checked.to),
+                }) }
+            }
+            Diagnostic::TypeCycle {} => ContractError::TypeCycle { type_: deeplink.from.map_or_else(String::new, |from| from.to_string()) },
+            Diagnostic::Unauthorized {} => ContractError::Unauthorized {},
         });
     }
 
-    let (mut dtype_, mut dfrom, mut dto): (Option<DeeplinkState>, Option<DeeplinkState>, Option<DeeplinkState>) = (None, None, None);
+    let (type_id, _) = checked.type_
+        .ok_or_else(|| ContractError::TypeNotExists { type_: deeplink.type_.to_string() })?;
+    Ok(ValidDeeplink {
+        type_: type_id,
+        from: checked.from.map(|(from_id, _)| from_id),
+        to: checked.to.map(|(to_id, _)| to_id),
+    })
+}
+
+/// Diagnostics of a deeplink as CreateDeeplinks would see it, authorization is
+/// reported when the sender is given
+pub(crate) fn diagnose_deeplink(
+    deps: Deps,
+    deeplink: &Deeplink,
+    package: Option<&str>,
+    sender: Option<&Addr>,
+) -> StdResult<Vec<Diagnostic>> {
+    let resolve = |deeplink_ref: &DeeplinkRef| resolve_deeplink(deps, deeplink_ref, package);
+    let parents = |type_: u64| type_parents(deps.storage, type_);
+    let mut checked = check_deeplink(deps, deeplink, &resolve, &parents).map_err(|err| match err {
+        ContractError::Std(err) => err,
+        err => StdError::generic_err(err.to_string()),
+    })?;
+
+    if let (Some(sender), Some((type_id, _))) = (sender, &checked.type_) {
+        let cfg = CONFIG.load(deps.storage)?;
+        let from = checked.from.as_ref().map(|(from, _)| *from);
+        if !cfg.can_create(sender.as_ref(), *type_id) || !can_declare(deps, &cfg, sender, *type_id, from)? {
+            checked.diagnostics.push(Diagnostic::Unauthorized {});
+        }
+    }
+    Ok(checked.diagnostics)
+}
+
+fn create_deeplink(
+    deps: DepsMut,
+    env: &Env,
+    creator: &Addr,
+    deeplink: Deeplink,
+    package: Option<&str>,
+) -> Result<u64, ContractError> {
+    let valid = validate_deeplink(deps.as_ref(), None, deeplink, package)?;
+
+    let cfg = CONFIG.load(deps.storage)?;
+    if !cfg.can_create(creator.as_ref(), valid.type_)
+        || !can_declare(deps.as_ref(), &cfg, creator, valid.type_, valid.from)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    let deeplink_state = valid.into_state(creator.clone(), env);
+
+    // Generate new ID
+    let id = ID.load(deps.storage)? + 1;
+    ID.save(deps.storage, &id)?;
+
+    // Save new Deeplink
+    deeplinks().save(deps.storage, id, &deeplink_state)?;
+
+    Ok(id)
+}
+
+pub fn execute_set_type_ends(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    type_: DeeplinkRef,
+    ends: TypeEnds,
+) -> Result<Response, ContractError> {
+    let (type_id, type_state) = resolve_deeplink(deps.as_ref(), &type_, None)?
+        .ok_or_else(|| ContractError::TypeNotExists { type_: type_.to_string() })?;
+
+    // Same permission as updating the type itself
+    let cfg = CONFIG.load(deps.storage)?;
+    if !cfg.can_modify_deeplink(info.sender.as_ref(), &type_state) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    TYPE_ENDS.save(deps.storage, type_id, &ends)?;
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "set_type_ends"),
+            attr("type", type_id.to_string()),
+        ])
+    )
+}
+
+/// Creates or replaces core deeplinks by name, called from migrate only
+pub fn migrate_core_deeplinks(
+    deps: DepsMut,
+    env: &Env,
+    core_deeplinks: Vec<NamedDeeplink>,
+) -> Result<Vec<u64>, ContractError> {
+    let mut ids = vec![];
+    for entry in core_deeplinks {
+        let name = entry.id.clone();
+        if split_name(&name).is_some() {
+            return Err(ContractError::InvalidName { name });
+        }
+        let valid = validate_deeplink(deps.as_ref(), Some(name.clone()), entry.into(), None)?;
+        let (id, deeplink_state) = match NAMED_DEEPLINKS.may_load(deps.storage, name.as_str())? {
+            Some(id) => {
+                let existing = deeplinks().load(deps.storage, id)?;
+                (id, DeeplinkState { type_: valid.type_, from: valid.from, to: valid.to, ..existing })
+            }
+            None => {
+                let id = ID.load(deps.storage)? + 1;
+                ID.save(deps.storage, &id)?;
+                (id, valid.into_state(env.contract.address.clone(), env))
+            }
+        };
+        deeplinks().save(deps.storage, id, &deeplink_state)?;
+        NAMED_DEEPLINKS.save(deps.storage, name.as_str(), &id)?;
+        DEEPLINK_NAMES.save(deps.storage, id, &name)?;
+        CORE_NAMES.save(deps.storage, name.as_str(), &true)?;
+        ids.push(id);
+    }
+    Ok(ids)
+}
+
+/// Global names belong to admins, package names to the package publisher
+fn check_name_owner(deps: Deps, sender: &Addr, name: &str) -> Result<(), ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let authorized = match split_name(name) {
+        Some((package, _)) => {
+            let package_state = PACKAGES.may_load(deps.storage, package)?
+                .ok_or_else(|| ContractError::PackageNotExists { package: package.to_string() })?;
+            cfg.can_modify(sender.as_ref()) || package_state.publisher == *sender
+        }
+        None => cfg.can_modify(sender.as_ref()),
+    };
+    if !authorized {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// Deeplinks with a core name only change through migrate and are never deleted
+fn check_not_core(storage: &dyn Storage, id: u64) -> Result<(), ContractError> {
+    for name in names_of(storage, id)? {
+        if split_name(&name).is_none() && is_core_name(storage, &name) {
+            return Err(ContractError::CoreName { name });
+        }
+    }
+    Ok(())
+}
+
+fn load_name(deps: Deps, name: &str) -> Result<u64, ContractError> {
+    NAMED_DEEPLINKS.may_load(deps.storage, name)?
+        .ok_or_else(|| ContractError::NameNotExists { name: name.to_string() })
+}
+
+/// Removes the canonical name and the aliases of a deeplink
+fn remove_names(deps: DepsMut, id: u64) -> StdResult<()> {
+    for name in names_of(deps.storage, id)? {
+        NAMED_DEEPLINKS.remove(deps.storage, name.as_str());
+        DEEPLINK_ALIASES.remove(deps.storage, (id, name.as_str()));
+    }
+    DEEPLINK_NAMES.remove(deps.storage, id);
+    Ok(())
+}
+
+/// Puts a plain name into the default package and checks the `package/name` form,
+/// names starting with `$` are left to batch placeholders
+fn qualify_name(name: &str, package: Option<&str>) -> Result<String, ContractError> {
+    let name = match (split_name(name), package) {
+        (None, Some(package)) => format!("{}/{}", package, name),
+        _ => name.to_string(),
+    };
+    let valid = match split_name(&name) {
+        Some((package, local)) => !package.is_empty() && !local.is_empty() && !local.contains('/') && !local.starts_with('$'),
+        None => !name.is_empty() && !name.starts_with('$'),
+    };
+    if !valid {
+        return Err(ContractError::InvalidName { name });
+    }
+    Ok(name)
+}
+
+pub fn execute_create_named_deeplink(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    name: String,
+    deeplink: Deeplink,
+    package: Option<String>,
+    upsert: bool,
+) -> Result<Response, ContractError> {
+    let name = qualify_name(&name, package.as_deref())?;
+    let package = split_name(&name).map(|(package, _)| package);
+    check_name_owner(deps.as_ref(), &info.sender, &name)?;
+
+    // Existing names are replaced only on request, core names never
+    let existing = NAMED_DEEPLINKS.may_load(deps.storage, name.as_str())?;
+    if let Some(id) = existing {
+        if !upsert {
+            return Err(ContractError::AlreadyExists { name });
+        }
+        check_not_core(deps.storage, id)?;
+    }
+
+    let valid = validate_deeplink(deps.as_ref(), Some(name.clone()), deeplink, package)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    if !can_declare(deps.as_ref(), &cfg, &info.sender, valid.type_, valid.from)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Existing name is updated in place so references to its id stay valid
+    let (id, deeplink_state) = match existing {
+        Some(id) => {
+            let existing = deeplinks().load(deps.storage, id)?;
+            (id, DeeplinkState { type_: valid.type_, from: valid.from, to: valid.to, ..existing })
+        }
+        None => {
+            // Generate new ID
+            let id = ID.load(deps.storage)? + 1;
+            ID.save(deps.storage, &id)?;
+            (id, valid.into_state(info.sender, &env))
+        }
+    };
+
+    // Save new Deeplink
+    deeplinks().save(deps.storage, id, &deeplink_state)?;
+
+    if existing.is_none() {
+        NAMED_DEEPLINKS.save(deps.storage, name.as_str(), &id)?;
+        DEEPLINK_NAMES.save(deps.storage, id, &name)?;
+    }
+
+    let data = CreateDeeplinksResponse {
+        deeplinks: vec![CreatedDeeplink { id: Uint64::new(id), name: Some(name) }],
+        failures: vec![],
+    };
+    Ok(Response::new()
+        .add_attribute("action", "create_named_deeplink")
+        .add_attributes(deeplink_attributes(deps.as_ref(), id)?)
+        .set_data(to_json_binary(&data)?)
+    )
+}
+
+pub fn execute_create_deeplink(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    deeplink: Deeplink,
+    package: Option<String>,
+) -> Result<Response, ContractError> {
+    let id = create_deeplink(deps.branch(), &env, &info.sender, deeplink, package.as_deref())?;
+
+    let data = CreateDeeplinksResponse {
+        deeplinks: vec![CreatedDeeplink { id: Uint64::new(id), name: None }],
+        failures: vec![],
+    };
+    Ok(Response::new()
+        .add_attribute("action", "create_deeplink")
+        .add_attributes(deeplink_attributes(deps.as_ref(), id)?)
+        .set_data(to_json_binary(&data)?)
+    )
+}
+
+/// Points the error at the entry of a batch message it came from
+fn entry_error(index: usize, error: ContractError) -> ContractError {
+    ContractError::BatchEntry { index: index as u32, error: Box::new(error) }
+}
+
+/// With `continue_on_error` invalid entries are skipped and reported in the data,
+/// otherwise the first one fails the message
+pub fn execute_create_deeplinks(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    deeplinks: Vec<Deeplink>,
+    package: Option<String>,
+    continue_on_error: bool,
+) -> Result<Response, ContractError> {
+    let mut response = Response::new().add_attribute("action", "create_deeplinks");
+    let mut created = vec![];
+    let mut failures = vec![];
+    for (index, deeplink) in deeplinks.into_iter().enumerate() {
+        // Nothing is written before an entry passes all checks, so skipping leaves no trace
+        match create_deeplink(deps.branch(), &env, &info.sender, deeplink, package.as_deref()) {
+            Ok(id) => {
+                response = response.add_attributes(deeplink_attributes(deps.as_ref(), id)?);
+                created.push(CreatedDeeplink { id: Uint64::new(id), name: None });
+            }
+            Err(error) if continue_on_error => {
+                failures.push(EntryFailure { index: index as u32, error: error.to_string() });
+            }
+            Err(error) => return Err(entry_error(index, error)),
+        }
+    }
+    if !failures.is_empty() {
+        response = response.add_attribute("failed", failures.len().to_string());
+    }
+
+    Ok(response.set_data(to_json_binary(&CreateDeeplinksResponse { deeplinks: created, failures })?))
+}
+
+/// Replaces a `$` reference with the id created earlier in the batch
+fn substitute(
+    placeholders: &BTreeMap<String, Uint64>,
+    deeplink_ref: DeeplinkRef,
+) -> Result<DeeplinkRef, ContractError> {
+    match deeplink_ref {
+        DeeplinkRef::Name(name) if name.starts_with('$') => match placeholders.get(&name) {
+            Some(id) => Ok(DeeplinkRef::Id(*id)),
+            None => Err(ContractError::UnknownPlaceholder { placeholder: name }),
+        },
+        deeplink_ref => Ok(deeplink_ref),
+    }
+}
+
+fn substitute_deeplink(
+    placeholders: &BTreeMap<String, Uint64>,
+    deeplink: Deeplink,
+) -> Result<Deeplink, ContractError> {
+    Ok(Deeplink {
+        type_: substitute(placeholders, deeplink.type_)?,
+        from: deeplink.from.map(|from| substitute(placeholders, from)).transpose()?,
+        to: deeplink.to.map(|to| substitute(placeholders, to)).transpose()?,
+    })
+}
+
+fn batch_id(
+    deps: Deps,
+    placeholders: &BTreeMap<String, Uint64>,
+    deeplink_ref: DeeplinkRef,
+    package: Option<&str>,
+) -> Result<Uint64, ContractError> {
+    match substitute(placeholders, deeplink_ref)? {
+        DeeplinkRef::Id(id) => Ok(id),
+        DeeplinkRef::Name(name) => match lookup_name(deps.storage, &name, package)? {
+            Some(id) => Ok(Uint64::new(id)),
+            None => Err(ContractError::NameNotExists { name }),
+        },
+    }
+}
+
+/// Applies one Batch operation, returns the id if it created a deeplink
+fn apply_batch_op(
+    deps: DepsMut,
+    env: &Env,
+    sender: &Addr,
+    index: usize,
+    op: BatchOp,
+    package: Option<&str>,
+    placeholders: &mut BTreeMap<String, Uint64>,
+) -> Result<Option<u64>, ContractError> {
+    match op {
+        BatchOp::Create { deeplink, placeholder } => {
+            let deeplink = substitute_deeplink(placeholders, deeplink)?;
+            let id = create_deeplink(deps, env, sender, deeplink, package)?;
+            placeholders.insert(format!("${}", index), Uint64::new(id));
+            if let Some(placeholder) = placeholder {
+                let key = format!("${}", placeholder);
+                if placeholder.is_empty() || placeholder.parse::<usize>().is_ok() || placeholders.contains_key(&key) {
+                    return Err(ContractError::InvalidPlaceholder { placeholder });
+                }
+                placeholders.insert(key, Uint64::new(id));
+            }
+            return Ok(Some(id));
+        }
+        BatchOp::Update { id, deeplink } => {
+            let id = batch_id(deps.as_ref(), placeholders, id, package)?;
+            let deeplink = substitute_deeplink(placeholders, deeplink)?;
+            update_deeplink(deps, sender, id, deeplink, package)?;
+        }
+        BatchOp::Delete { id, mode } => {
+            let id = batch_id(deps.as_ref(), placeholders, id, package)?;
+            delete_deeplink(deps, sender, id.u64(), mode.unwrap_or(DeleteMode::Restrict))?;
+        }
+        BatchOp::SetValue { id, value } => {
+            let id = batch_id(deps.as_ref(), placeholders, id, package)?;
+            set_value(deps, env, sender, id, value)?;
+        }
+    }
+    Ok(None)
+}
+
+/// Any failed operation fails the message, so the writes of earlier ones are reverted with it
+pub fn execute_batch(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    ops: Vec<BatchOp>,
+    package: Option<String>,
+) -> Result<Response, ContractError> {
+    let package = package.as_deref();
+    let mut response = Response::new().add_attribute("action", "batch");
+    let mut placeholders = BTreeMap::new();
+    for (index, op) in ops.into_iter().enumerate() {
+        let created = apply_batch_op(deps.branch(), &env, &info.sender, index, op, package, &mut placeholders)
+            .map_err(|error| entry_error(index, error))?;
+        if let Some(id) = created {
+            response = response.add_attributes(deeplink_attributes(deps.as_ref(), id)?);
+        }
+    }
+
+    Ok(response.set_data(to_json_binary(&BatchResponse { placeholders })?))
+}
+
+pub fn execute_rename_deeplink(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    name: String,
+    new_name: String,
+) -> Result<Response, ContractError> {
+    let id = load_name(deps.as_ref(), &name)?;
+    let new_name = qualify_name(&new_name, None)?;
+    check_name_owner(deps.as_ref(), &info.sender, &name)?;
+    check_name_owner(deps.as_ref(), &info.sender, &new_name)?;
+    check_not_core(deps.storage, id)?;
+    if NAMED_DEEPLINKS.has(deps.storage, new_name.as_str()) {
+        return Err(ContractError::AlreadyExists { name: new_name });
+    }
+
+    NAMED_DEEPLINKS.remove(deps.storage, name.as_str());
+    NAMED_DEEPLINKS.save(deps.storage, new_name.as_str(), &id)?;
+    if DEEPLINK_NAMES.may_load(deps.storage, id)?.as_ref() == Some(&name) {
+        DEEPLINK_NAMES.save(deps.storage, id, &new_name)?;
+    } else {
+        DEEPLINK_ALIASES.remove(deps.storage, (id, name.as_str()));
+        DEEPLINK_ALIASES.save(deps.storage, (id, new_name.as_str()), &true)?;
+    }
+
+    // Installed deeplinks are named again by their entry on activation
+    if let Some(package) = DEEPLINK_PACKAGES.may_load(deps.storage, id)? {
+        let mut entry = PACKAGE_IDS.load(deps.storage, (package.as_str(), id))?;
+        if entry.name == name {
+            entry.name = new_name.clone();
+            PACKAGE_IDS.save(deps.storage, (package.as_str(), id), &entry)?;
+        }
+    }
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "rename_deeplink"),
+            attr("id", id.to_string()),
+            attr("name", name),
+            attr("new_name", new_name),
+        ])
+    )
+}
+
+pub fn execute_add_alias(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    id: Uint64,
+    alias: String,
+) -> Result<Response, ContractError> {
+    resolve_deeplink(deps.as_ref(), &DeeplinkRef::Id(id), None)?
+        .ok_or(ContractError::DeeplinkNotExists { id })?;
+    let alias = qualify_name(&alias, None)?;
+    check_name_owner(deps.as_ref(), &info.sender, &alias)?;
+    if NAMED_DEEPLINKS.has(deps.storage, alias.as_str()) {
+        return Err(ContractError::AlreadyExists { name: alias });
+    }
+
+    // A deeplink without names gets it as the canonical one
+    NAMED_DEEPLINKS.save(deps.storage, alias.as_str(), &id.u64())?;
+    if DEEPLINK_NAMES.has(deps.storage, id.u64()) {
+        DEEPLINK_ALIASES.save(deps.storage, (id.u64(), alias.as_str()), &true)?;
+    } else {
+        DEEPLINK_NAMES.save(deps.storage, id.u64(), &alias)?;
+    }
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "add_alias"),
+            attr("id", id.to_string()),
+            attr("alias", alias),
+        ])
+    )
+}
+
+pub fn execute_remove_alias(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    alias: String,
+) -> Result<Response, ContractError> {
+    let id = load_name(deps.as_ref(), &alias)?;
+    check_name_owner(deps.as_ref(), &info.sender, &alias)?;
+    if !DEEPLINK_ALIASES.has(deps.storage, (id, alias.as_str())) {
+        return Err(ContractError::NotAlias { name: alias });
+    }
+
+    NAMED_DEEPLINKS.remove(deps.storage, alias.as_str());
+    DEEPLINK_ALIASES.remove(deps.storage, (id, alias.as_str()));
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "remove_alias"),
+            attr("id", id.to_string()),
+            attr("alias", alias),
+        ])
+    )
+}
+
+pub fn execute_set_canonical_name(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    name: String,
+) -> Result<Response, ContractError> {
+    let id = load_name(deps.as_ref(), &name)?;
+    check_name_owner(deps.as_ref(), &info.sender, &name)?;
+    if !DEEPLINK_ALIASES.has(deps.storage, (id, name.as_str())) {
+        return Err(ContractError::NotAlias { name });
+    }
+    check_not_core(deps.storage, id)?;
+    let canonical = DEEPLINK_NAMES.load(deps.storage, id)?;
+
+    DEEPLINK_ALIASES.remove(deps.storage, (id, name.as_str()));
+    DEEPLINK_ALIASES.save(deps.storage, (id, canonical.as_str()), &true)?;
+    DEEPLINK_NAMES.save(deps.storage, id, &name)?;
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "set_canonical_name"),
+            attr("id", id.to_string()),
+            attr("name", name),
+        ])
+    )
+}
+
+fn update_deeplink(
+    deps: DepsMut,
+    sender: &Addr,
+    id: Uint64,
+    deeplink: Deeplink,
+    package: Option<&str>,
+) -> Result<(), ContractError> {
+    let existing = deeplinks().may_load(deps.storage, id.u64())?
+        .ok_or(ContractError::DeeplinkNotExists { id })?;
+    if DELETED_IDS.may_load(deps.storage, id.u64())?.unwrap_or(false) {
+        return Err(ContractError::DeletedDeeplink { id });
+    }
+
+    let cfg = CONFIG.load(deps.storage)?;
+    if !cfg.can_modify_deeplink(sender.as_ref(), &existing) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    check_not_core(deps.storage, id.u64())?;
+
+    // An updated Extends link no longer declares its old parent
+    let extends = NAMED_DEEPLINKS.may_load(deps.storage, "Extends")?;
+    let resolve = |deeplink_ref: &DeeplinkRef| resolve_deeplink(deps.as_ref(), deeplink_ref, package);
+    let parents = |type_: u64| -> StdResult<Vec<u64>> {
+        let mut parents = type_parents(deps.storage, type_)?;
+        if Some(existing.type_) == extends && existing.from == Some(type_) {
+            if let Some(position) = parents.iter().position(|parent| Some(*parent) == existing.to) {
+                parents.remove(position);
+            }
+        }
+        Ok(parents)
+    };
+    let valid = validate_deeplink_with(deps.as_ref(), Some(id.to_string()), deeplink, &resolve, &parents)?;
+    if valid.type_ != existing.type_ && !cfg.can_create(sender.as_ref(), valid.type_) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !can_declare(deps.as_ref(), &cfg, sender, valid.type_, valid.from)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Names point to the id, so they follow the update
+    let deeplink_state = DeeplinkState { type_: valid.type_, from: valid.from, to: valid.to, ..existing };
+    deeplinks().save(deps.storage, id.u64(), &deeplink_state)?;
+    Ok(())
+}
+
+pub fn execute_update_deeplink(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    id: Uint64,
+    deeplink: Deeplink,
+    package: Option<String>,
+) -> Result<Response, ContractError> {
+    update_deeplink(deps, &info.sender, id, deeplink, package.as_deref())?;
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "update_deeplink"),
+            attr("id", id.to_string())
+        ])
+    )
+}
+
+pub fn execute_delete_deeplink(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    id: Uint64,
+    mode: Option<DeleteMode>,
+) -> Result<Response, ContractError> {
+    let affected = delete_deeplink(deps, &info.sender, id.u64(), mode.unwrap_or(DeleteMode::Restrict))?;
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "delete_deeplink"),
+            attr("id", id.to_string()),
+            attr("affected_ids", join_ids(&affected)),
+        ])
+    )
+}
+
+pub fn execute_restore_deeplink(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    id: Uint64,
+) -> Result<Response, ContractError> {
+    let deeplink_state = deeplinks().may_load(deps.storage, id.u64())?
+        .ok_or(ContractError::DeeplinkNotExists { id })?;
+    if !DELETED_IDS.has(deps.storage, id.u64()) {
+        return Err(ContractError::DeeplinkNotDeleted { id });
+    }
+
+    let cfg = CONFIG.load(deps.storage)?;
+    if !cfg.can_modify_deeplink(info.sender.as_ref(), &deeplink_state) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Endpoints may have been deleted or changed since
+    validate_deeplink(deps.as_ref(), Some(id.to_string()), Deeplink {
+        type_: deeplink_state.type_.into(),
+        from: deeplink_state.from.map(Into::into),
+        to: deeplink_state.to.map(Into::into),
+    }, None)?;
+
+    DELETED_IDS.remove(deps.storage, id.u64());
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "restore_deeplink"),
+            attr("id", id.to_string())
+        ])
+    )
+}
+
+pub fn execute_purge_deeplinks(
+    mut deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if !cfg.is_admin(info.sender.as_ref()) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let limit = limit.unwrap_or(DEFAULT_PURGE_LIMIT).min(MAX_PURGE_LIMIT) as usize;
+    let ids = DELETED_IDS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<BTreeSet<u64>>>()?;
+
+    for id in ids.iter() {
+        let id = *id;
+        check_not_core(deps.storage, id)?;
+        deeplinks().remove(deps.storage, id)?;
+        DELETED_IDS.remove(deps.storage, id);
+        NUMBERS_VALUES.remove(deps.storage, id);
+        STRINGS_VALUES.remove(deps.storage, id);
+        OBJECTS_VALUES.remove(deps.storage, id);
+        remove_names(deps.branch(), id)?;
+        if let Some(package) = DEEPLINK_PACKAGES.may_load(deps.storage, id)? {
+            PACKAGE_IDS.remove(deps.storage, (package.as_str(), id));
+            DEEPLINK_PACKAGES.remove(deps.storage, id);
+        }
+    }
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "purge_deeplinks"),
+            attr("purged_ids", join_ids(&ids)),
+        ])
+    )
+}
+
+pub fn execute_update_admins(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    new_admins: Vec<String>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if !cfg.can_modify(info.sender.as_ref()) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let admins = map_validate(deps.api, &new_admins)?;
+    CONFIG.update(deps.storage, |mut cfg| -> StdResult<_> {
+        cfg.admins = admins;
+        Ok(cfg)
+    })?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "update_admins")]))
+}
 
-    dtype_ = NAMED_DEEPLINKS.may_load(deps.storage, deeplink.type_.as_str())?;
-    if dtype_.is_none() {
-        return Err(ContractError::TypeNotExists { type_: deeplink.type_.clone() });
+pub fn execute_update_executors(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    new_executors: Vec<String>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if !cfg.can_modify(info.sender.as_ref()) {
+        return Err(ContractError::Unauthorized {});
     }
-    if deeplink.from.is_some() {
-        dfrom = NAMED_DEEPLINKS.may_load(deps.storage, deeplink.clone().from.unwrap().as_str())?;
-        if dfrom.is_none() {
-            return Err(ContractError::FromNotExists { from: deeplink.from.unwrap_or_else(|| "_".to_string()) });
-        }
+
+    let executors = map_validate(deps.api, &new_executors)?;
+    CONFIG.update(deps.storage, |mut cfg| -> StdResult<_> {
+        cfg.executors = executors;
+        Ok(cfg)
+    })?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "update_executors")]))
+}
+
+fn load_value_owner(
+    deps: Deps,
+    sender: &Addr,
+    id: Uint64,
+) -> Result<DeeplinkState, ContractError> {
+    let deeplink_state = deeplinks().may_load(deps.storage, id.u64())?
+        .ok_or(ContractError::DeeplinkNotExists { id })?;
+    if DELETED_IDS.has(deps.storage, id.u64()) {
+        return Err(ContractError::DeletedDeeplink { id });
     }
-    if deeplink.to.is_some() {
-        dto = NAMED_DEEPLINKS.may_load(deps.storage, deeplink.clone().to.unwrap().as_str())?;
-        if dto.is_none() {
-            return Err(ContractError::ToNotExists { to: deeplink.to.unwrap_or_else(|| "_".to_string()) });
-        }
+
+    let cfg = CONFIG.load(deps.storage)?;
+    if !cfg.can_modify_deeplink(sender.as_ref(), &deeplink_state) {
+        return Err(ContractError::Unauthorized {});
     }
+    Ok(deeplink_state)
+}
 
-    // Additional validation for type conflicts
-    if let (Some(ref from), Some(ref to)) = (&deeplink.from, &deeplink.to) {
-        if dtype_.clone().unwrap().from.ne(&"Any") && dtype_.clone().unwrap().from.ne(&dfrom.clone().unwrap().type_) {
-            return Err(ContractError::TypeConflict {
-                id: id.unwrap_or_else(|| "_".to_string()),
-                type_: deeplink.clone().type_,
-                from: deeplink.clone().from.unwrap_or_else(|| "_".to_string()),
-                to: deeplink.clone().to.unwrap_or_else(|| "_".to_string()),
-                expected_type: deeplink.clone().type_,
-                expected_from: dtype_.clone().unwrap().from,
-                expected_to: dtype_.clone().unwrap().to,
-                received_type: deeplink.clone().type_,
-                received_from: dfrom.clone().unwrap().type_,
-                received_to: dto.clone().unwrap().type_,
-            });
+fn display_name(deps: Deps, id: u64) -> StdResult<String> {
+    Ok(DEEPLINK_NAMES.may_load(deps.storage, id)?.unwrap_or_else(|| id.to_string()))
+}
+
+/// Value and Extends links change what every deeplink of their from type accepts,
+/// so only senders allowed to modify that type declare one
+fn can_declare(deps: Deps, cfg: &Config, sender: &Addr, type_: u64, from: Option<u64>) -> StdResult<bool> {
+    let declaring = [
+        NAMED_DEEPLINKS.may_load(deps.storage, "Value")?,
+        NAMED_DEEPLINKS.may_load(deps.storage, "Extends")?,
+    ];
+    let from = match from {
+        Some(from) if declaring.contains(&Some(type_)) => from,
+        _ => return Ok(true),
+    };
+    // Types not written yet are declared by the same install
+    Ok(match deeplinks().may_load(deps.storage, from)? {
+        Some(from_state) => cfg.can_modify_deeplink(sender.as_ref(), &from_state),
+        None => true,
+    })
+}
+
+/// Value links (type: Value, from: <type>, to: String | Number | Object) declare
+/// which kind of value deeplinks of a type hold, subtypes inherit the kind of
+/// the nearest declaring parent
+fn declared_value_kind(deps: Deps, type_: u64) -> StdResult<Option<u64>> {
+    let value_type = match NAMED_DEEPLINKS.may_load(deps.storage, "Value")? {
+        Some(value_type) => value_type,
+        None => return Ok(None),
+    };
+    let mut visited = BTreeSet::new();
+    let mut queue = VecDeque::from([type_]);
+    while let Some(next) = queue.pop_front() {
+        if !visited.insert(next) {
+            continue;
+        }
+        let value_links = deeplinks().idx.type_from.prefix((value_type, next));
+        for item in value_links.range(deps.storage, None, None, Order::Ascending) {
+            let (value_id, value_link) = item?;
+            if !DELETED_IDS.has(deps.storage, value_id) {
+                return Ok(value_link.to);
+            }
         }
+        queue.extend(type_parents(deps.storage, next)?);
+    }
+    Ok(None)
+}
+
+fn check_value_kind(
+    deps: Deps,
+    id: Uint64,
+    deeplink_state: &DeeplinkState,
+    value: &Value,
+) -> Result<(), ContractError> {
+    let declared = declared_value_kind(deps, deeplink_state.type_)?;
+    let expected = declared.ok_or(ContractError::ValueNotDeclared {
+        id,
+        type_: display_name(deps, deeplink_state.type_)?,
+    })?;
+
+    if NAMED_DEEPLINKS.may_load(deps.storage, value.kind())? != Some(expected) {
+        return Err(ContractError::ValueKindMismatch {
+            id,
+            type_: display_name(deps, deeplink_state.type_)?,
+            expected: display_name(deps, expected)?,
+            received: value.kind().to_string(),
+        });
+    }
+    Ok(())
+}
 
-        if dtype_.clone().unwrap().to.ne(&"Any") && dtype_.clone().unwrap().to.ne(&dto.clone().unwrap().type_) {
-            return Err(ContractError::TypeConflict {
-                id: id.unwrap_or_else(|| "_".to_string()),
-                type_: deeplink.clone().type_,
-                from: deeplink.clone().from.unwrap_or_else(|| "_".to_string()),
-                to: deeplink.clone().to.unwrap_or_else(|| "_".to_string()),
-                expected_type: deeplink.clone().type_,
-                expected_from: dtype_.clone().unwrap().from,
-                expected_to: dtype_.clone().unwrap().to,
-                received_type: deeplink.clone().type_,
-                received_from: dfrom.clone().unwrap().type_,
-                received_to: dto.clone().unwrap().type_,
+fn save_value(
+    deps: DepsMut,
+    env: &Env,
+    id: Uint64,
+    value: Value,
+) -> Result<(), ContractError> {
+    if let Value::Object(object) = &value {
+        let cfg = CONFIG.load(deps.storage)?;
+        if object.len() > cfg.max_object_size as usize {
+            return Err(ContractError::ObjectTooLarge {
+                id,
+                size: object.len() as u64,
+                max: cfg.max_object_size,
             });
         }
+        validate_object(object).map_err(|reason| ContractError::InvalidObject { reason })?;
     }
 
+    // A deeplink holds a single value, so a new kind replaces the old one
+    let id = id.u64();
+    NUMBERS_VALUES.remove(deps.storage, id);
+    STRINGS_VALUES.remove(deps.storage, id);
+    OBJECTS_VALUES.remove(deps.storage, id);
+    match value {
+        Value::Number(number) => NUMBERS_VALUES.save(deps.storage, id, &(env.block.height, number.u64()))?,
+        Value::String(string) => STRINGS_VALUES.save(deps.storage, id, &(env.block.height, string))?,
+        Value::Object(object) => OBJECTS_VALUES.save(deps.storage, id, &(env.block.height, object))?,
+    }
     Ok(())
 }
 
-fn create_deeplink(
+fn set_value(
     deps: DepsMut,
-    deeplink: Deeplink
-) -> Result<u64, ContractError> {
-    validate_deeplink(deps.as_ref(), None, deeplink.clone())?;
+    env: &Env,
+    sender: &Addr,
+    id: Uint64,
+    value: Value,
+) -> Result<(), ContractError> {
+    let deeplink_state = load_value_owner(deps.as_ref(), sender, id)?;
+    if load_value(deps.storage, id.u64())?.is_some() {
+        return Err(ContractError::ValueAlreadySet { id });
+    }
+    check_value_kind(deps.as_ref(), id, &deeplink_state, &value)?;
 
-    // Generate new ID
-    let id = ID.load(deps.storage)? + 1;
-    ID.save(deps.storage, &id)?;
+    save_value(deps, env, id, value)
+}
 
-    // Save new Deeplink
-    let type_ = deeplink.clone().type_;
-    let deeplink_state = DeeplinkState {
-        type_: deeplink.type_.clone(),
-        from: deeplink.from.unwrap_or_else(|| "Any".to_string()),
-        to: deeplink.to.unwrap_or_else(|| "Any".to_string()),
-    };
-    DEEPLINKS.save(deps.storage, id, &deeplink_state)?;
+pub fn execute_set_value(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: Uint64,
+    value: Value,
+) -> Result<Response, ContractError> {
+    set_value(deps, &env, &info.sender, id, value)?;
 
-    Ok(id)
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "set_value"),
+            attr("id", id.to_string())
+        ])
+    )
 }
 
-pub fn execute_create_named_deeplink(
+pub fn execute_update_value(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: Uint64,
+    value: Value,
+) -> Result<Response, ContractError> {
+    let deeplink_state = load_value_owner(deps.as_ref(), &info.sender, id)?;
+    if load_value(deps.storage, id.u64())?.is_none() {
+        return Err(ContractError::ValueNotSet { id });
+    }
+    check_value_kind(deps.as_ref(), id, &deeplink_state, &value)?;
+
+    save_value(deps, &env, id, value)?;
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "update_value"),
+            attr("id", id.to_string())
+        ])
+    )
+}
+
+pub fn execute_clear_value(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    name: String,
-    deeplink: Deeplink,
+    id: Uint64,
 ) -> Result<Response, ContractError> {
-    let cfg = CONFIG.load(deps.storage)?;
-    if !cfg.can_modify(info.sender.as_ref()) {
-        return Err(ContractError::Unauthorized {});
+    load_value_owner(deps.as_ref(), &info.sender, id)?;
+    if load_value(deps.storage, id.u64())?.is_none() {
+        return Err(ContractError::ValueNotSet { id });
     }
 
-    validate_deeplink(deps.as_ref(), Some(name.clone()), deeplink.clone())?;
+    NUMBERS_VALUES.remove(deps.storage, id.u64());
+    STRINGS_VALUES.remove(deps.storage, id.u64());
+    OBJECTS_VALUES.remove(deps.storage, id.u64());
 
-    // Generate new ID
-    let id = ID.load(deps.storage)? + 1;
-    ID.save(deps.storage, &id)?;
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "clear_value"),
+            attr("id", id.to_string())
+        ])
+    )
+}
 
-    // Save new Deeplink
-    // let type_ = deeplink.clone().type_;
-    let deeplink_state = DeeplinkState {
-        type_: deeplink.type_.clone(),
-        from: deeplink.from.unwrap_or_else(|| "Any".to_string()),
-        to: deeplink.to.unwrap_or_else(|| "Any".to_string()),
-    };
-    DEEPLINKS.save(deps.storage, id, &deeplink_state)?;
+fn create_package(
+    mut deps: DepsMut,
+    env: &Env,
+    publisher: &Addr,
+    name: &str,
+) -> Result<PackageState, ContractError> {
+    if name.is_empty() || name.contains('/') {
+        return Err(ContractError::InvalidName { name: name.to_string() });
+    }
+    if PACKAGES.has(deps.storage, name) {
+        return Err(ContractError::PackageAlreadyExists { package: name.to_string() });
+    }
+
+    let namespace = Deeplink { type_: "PackageNamespace".into(), from: None, to: None };
+    let id = create_deeplink(deps.branch(), env, publisher, namespace, None)?;
+    let value = Value::String(name.to_string());
+    check_value_kind(deps.as_ref(), Uint64::new(id), &deeplinks().load(deps.storage, id)?, &value)?;
+    save_value(deps, env, Uint64::new(id), value)?;
+    let package_state = PackageState { id, publisher: publisher.clone(), version: None };
+    Ok(package_state)
+}
+
+/// Dependencies must match their active versions and the version must match
+/// what the dependents of the package require
+fn check_dependencies(
+    deps: Deps,
+    package: &str,
+    version: &str,
+    dependencies: &[PackageDependency],
+) -> Result<(), ContractError> {
+    for dependency in dependencies {
+        let required = VersionReq::parse(&dependency.version)?;
+        let active = PACKAGES.may_load(deps.storage, dependency.package.as_str())?
+            .and_then(|package_state| package_state.version)
+            .ok_or_else(|| ContractError::DependencyMissing {
+                package: dependency.package.clone(),
+                required: dependency.version.clone(),
+            })?;
+        if !required.matches(&Version::parse(&active)?) {
+            return Err(ContractError::DependencyIncompatible {
+                package: dependency.package.clone(),
+                required: dependency.version.clone(),
+                version: active,
+            });
+        }
+    }
 
-    NAMED_DEEPLINKS.save(deps.storage, name.as_str(), &deeplink_state)?;
+    let parsed = Version::parse(version)?;
+    for item in PACKAGE_DEPENDENTS.prefix(package).range(deps.storage, None, None, Order::Ascending) {
+        let (dependent, required) = item?;
+        if dependent != package && !VersionReq::parse(&required)?.matches(&parsed) {
+            return Err(ContractError::DependencyIncompatible {
+                package: package.to_string(),
+                required,
+                version: version.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn activate_package_version(
+    mut deps: DepsMut,
+    env: &Env,
+    sender: &Addr,
+    package: &str,
+    version: &str,
+) -> Result<(), ContractError> {
+    let mut package_state = PACKAGES.load(deps.storage, package)?;
+    let version_state = PACKAGE_VERSIONS.load(deps.storage, (package, version))?;
+    let entries = PACKAGE_IDS
+        .prefix(package)
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    if let Some(active) = &package_state.version {
+        for (id, entry) in entries.iter().filter(|(_, entry)| &entry.version == active) {
+            if NAMED_DEEPLINKS.may_load(deps.storage, entry.name.as_str())? == Some(*id) {
+                NAMED_DEEPLINKS.remove(deps.storage, entry.name.as_str());
+                DEEPLINK_NAMES.remove(deps.storage, *id);
+            }
+        }
+        for dependency in PACKAGE_VERSIONS.load(deps.storage, (package, active.as_str()))?.dependencies {
+            PACKAGE_DEPENDENTS.remove(deps.storage, (dependency.package.as_str(), package));
+        }
+    }
+    for (id, entry) in entries.iter().filter(|(_, entry)| entry.version == version && !is_local_name(&entry.name)) {
+        NAMED_DEEPLINKS.save(deps.storage, entry.name.as_str(), id)?;
+        DEEPLINK_NAMES.save(deps.storage, *id, &entry.name)?;
+    }
+    for dependency in version_state.dependencies.iter() {
+        PACKAGE_DEPENDENTS.save(deps.storage, (dependency.package.as_str(), package), &dependency.version)?;
+    }
+
+    // A single PackageActive link from the namespace follows the active version
+    let package_active = NAMED_DEEPLINKS.load(deps.storage, "PackageActive")?;
+    let mut active_link = None;
+    let links = deeplinks().idx.type_from.prefix((package_active, package_state.id));
+    for item in links.range(deps.storage, None, None, Order::Ascending) {
+        let (link_id, link) = item?;
+        if !DELETED_IDS.has(deps.storage, link_id) {
+            active_link = Some((link_id, link));
+            break;
+        }
+    }
+    match active_link {
+        Some((link_id, link)) => {
+            deeplinks().save(deps.storage, link_id, &DeeplinkState { to: Some(version_state.id), ..link })?;
+        }
+        None => {
+            let link = Deeplink {
+                type_: "PackageActive".into(),
+                from: Some(package_state.id.into()),
+                to: Some(version_state.id.into()),
+            };
+            create_deeplink(deps.branch(), env, sender, link, None)?;
+        }
+    }
 
-    Ok(Response::new().add_attributes(vec![attr("action", "create_named_deeplink")]))
+    package_state.version = Some(version.to_string());
+    PACKAGES.save(deps.storage, package, &package_state)?;
+    Ok(())
 }
 
-pub fn execute_create_deeplink(
-    deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
-    deeplink: Deeplink
+pub fn execute_create_package(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    name: String,
 ) -> Result<Response, ContractError> {
-    create_deeplink(deps, deeplink)?;
-    Ok(Response::new().add_attributes(vec![attr("action", "create_deeplink")]))
+    let package_state = create_package(deps.branch(), &env, &info.sender, &name)?;
+    PACKAGES.save(deps.storage, name.as_str(), &package_state)?;
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "create_package"),
+            attr("package", name),
+            attr("id", package_state.id.to_string())
+        ])
+    )
 }
 
-pub fn execute_create_deeplinks(
+/// Orders entries so that referenced entries come first, entries left in
+/// cycles follow in manifest order
+pub(crate) fn manifest_order(references: &[Vec<usize>]) -> Vec<usize> {
+    let mut order = Vec::with_capacity(references.len());
+    let mut placed = vec![false; references.len()];
+    loop {
+        let mut progress = false;
+        for (index, refs) in references.iter().enumerate() {
+            if !placed[index] && refs.iter().all(|r| *r == index || placed[*r]) {
+                order.push(index);
+                placed[index] = true;
+                progress = true;
+            }
+        }
+        if !progress {
+            break;
+        }
+    }
+    order.extend((0..references.len()).filter(|index| !placed[*index]));
+    order
+}
+
+pub fn execute_install_package(
     mut deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
-    deeplinks: Vec<Deeplink>
+    env: Env,
+    info: MessageInfo,
+    package: String,
+    version: String,
+    manifest: Vec<NamedDeeplink>,
+    dependencies: Vec<PackageDependency>,
 ) -> Result<Response, ContractError> {
-    for deeplink in deeplinks {
-        create_deeplink(deps.branch(), deeplink)?;
+    Version::parse(&version)?;
+    if package.is_empty() || package.contains('/') {
+        return Err(ContractError::InvalidName { name: package });
+    }
+
+    let cfg = CONFIG.load(deps.storage)?;
+    let existing = PACKAGES.may_load(deps.storage, package.as_str())?;
+    if let Some(package_state) = &existing {
+        if !cfg.can_modify(info.sender.as_ref()) && package_state.publisher != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+    }
+    if PACKAGE_VERSIONS.has(deps.storage, (package.as_str(), version.as_str())) {
+        return Err(ContractError::PackageVersionExists { package, version });
+    }
+    check_dependencies(deps.as_ref(), &package, &version, &dependencies)?;
+    let active = existing.as_ref().and_then(|package_state| package_state.version.clone());
+
+    // Entry names are qualified with the package and can't point into another one
+    let mut names = Vec::with_capacity(manifest.len());
+    let mut local = BTreeMap::new();
+    for (index, entry) in manifest.iter().enumerate() {
+        let name = qualify_name(&entry.id, Some(&package))?;
+        if split_name(&name).map(|(entry_package, _)| entry_package) != Some(package.as_str()) {
+            return Err(ContractError::InvalidName { name });
+        }
+        // Names of the active version move to the new one
+        let replaced = match NAMED_DEEPLINKS.may_load(deps.storage, name.as_str())? {
+            Some(id) if !is_local_name(&name) => matches!(
+                PACKAGE_IDS.may_load(deps.storage, (package.as_str(), id))?,
+                Some(entry) if Some(&entry.version) == active.as_ref()
+            ),
+            _ => true,
+        };
+        if local.contains_key(&name) || !replaced {
+            return Err(ContractError::AlreadyExists { name });
+        }
+        local.insert(name.clone(), index);
+        names.push(name);
+    }
+    let find_local = |name: &str| -> Option<usize> {
+        match split_name(name) {
+            Some(_) => local.get(name).copied(),
+            None => local.get(&format!("{}/{}", package, name)).copied(),
+        }
+    };
+
+    let references = manifest.iter()
+        .map(|entry| [Some(&entry.type_), entry.from.as_ref(), entry.to.as_ref()]
+            .iter()
+            .flatten()
+            .filter_map(|name| find_local(name))
+            .collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let order = manifest_order(&references);
+
+    // The namespace is created first, a failed install reverts it with the rest
+    let package_state = match existing {
+        Some(package_state) => package_state,
+        None => create_package(deps.branch(), &env, &info.sender, &package)?,
+    };
+    let first_id = ID.load(deps.storage)? + 1;
+    let mut ids = vec![0; manifest.len()];
+    for (position, index) in order.iter().enumerate() {
+        ids[*index] = first_id + position as u64;
+    }
+
+    // Validate every entry before writing, against the entries of the manifest
+    let validated = {
+        let deps = deps.as_ref();
+        let resolve_id = |name: &str| -> StdResult<Option<u64>> {
+            match find_local(name) {
+                Some(index) => Ok(Some(ids[index])),
+                None => lookup_name(deps.storage, name, Some(&package)),
+            }
+        };
+        let mut pending = BTreeMap::new();
+        for (index, entry) in manifest.iter().enumerate() {
+            if let Some(type_) = resolve_id(&entry.type_)? {
+                let from = entry.from.as_deref().map(resolve_id).transpose()?.flatten();
+                let to = entry.to.as_deref().map(resolve_id).transpose()?.flatten();
+                pending.insert(ids[index], ValidDeeplink { type_, from, to }.into_state(info.sender.clone(), &env));
+            }
+        }
+
+        let resolve = |deeplink_ref: &DeeplinkRef| -> Result<Option<(u64, DeeplinkState)>, ContractError> {
+            if let DeeplinkRef::Name(name) = deeplink_ref {
+                if let Some(index) = find_local(name) {
+                    return Ok(pending.get(&ids[index]).map(|state| (ids[index], state.clone())));
+                }
+            }
+            resolve_deeplink(deps, deeplink_ref, Some(&package))
+        };
+        // Parent types declared by the manifest count as well
+        let extends = NAMED_DEEPLINKS.may_load(deps.storage, "Extends")?;
+        let parents = |type_: u64| -> StdResult<Vec<u64>> {
+            let mut parents = type_parents(deps.storage, type_)?;
+            parents.extend(pending.values()
+                .filter(|state| Some(state.type_) == extends && state.from == Some(type_))
+                .filter_map(|state| state.to));
+            Ok(parents)
+        };
+        order.iter()
+            .map(|index| {
+                let name = Some(names[*index].clone());
+                let valid = validate_deeplink_with(deps, name, manifest[*index].clone().into(), &resolve, &parents)?;
+                if !cfg.can_create(info.sender.as_ref(), valid.type_)
+                    || !can_declare(deps, &cfg, &info.sender, valid.type_, valid.from)? {
+                    return Err(ContractError::Unauthorized {});
+                }
+                Ok((*index, valid))
+            })
+            .collect::<Result<Vec<_>, ContractError>>()?
+    };
+
+    for (index, valid) in validated {
+        let id = ids[index];
+        deeplinks().save(deps.storage, id, &valid.into_state(info.sender.clone(), &env))?;
+        let entry = PackageEntry { version: version.clone(), name: names[index].clone() };
+        PACKAGE_IDS.save(deps.storage, (package.as_str(), id), &entry)?;
+        DEEPLINK_PACKAGES.save(deps.storage, id, &package)?;
+    }
+    ID.save(deps.storage, &(first_id + manifest.len() as u64 - 1))?;
+
+    // Value kinds can be declared by the manifest itself, so values are checked
+    // once the deeplinks are written, a failure still reverts the whole install
+    for (index, entry) in manifest.iter().enumerate() {
+        if let Some(value) = &entry.value {
+            let id = Uint64::new(ids[index]);
+            let deeplink_state = deeplinks().load(deps.storage, id.u64())?;
+            let value = match &value.value {
+                ManifestValueContent::Number(number) => Value::Number(Uint64::new(*number)),
+                ManifestValueContent::String(string) => {
+                    let object = NAMED_DEEPLINKS.may_load(deps.storage, "Object")?;
+                    if object.is_some() && declared_value_kind(deps.as_ref(), deeplink_state.type_)? == object {
+                        Value::Object(string.clone())
+                    } else {
+                        Value::String(string.clone())
+                    }
+                }
+            };
+            check_value_kind(deps.as_ref(), id, &deeplink_state, &value)?;
+            save_value(deps.branch(), &env, id, value)?;
+        }
     }
-    Ok(Response::new().add_attributes(vec![attr("action", "create_deeplinks")]))
+
+    // Versions are Package deeplinks linked from the namespace with PackageVersion
+    PACKAGES.save(deps.storage, package.as_str(), &package_state)?;
+    let version_deeplink = Deeplink { type_: "Package".into(), from: None, to: None };
+    let version_id = create_deeplink(deps.branch(), &env, &info.sender, version_deeplink, None)?;
+    let version_value = Value::String(package.clone());
+    let version_state = deeplinks().load(deps.storage, version_id)?;
+    check_value_kind(deps.as_ref(), Uint64::new(version_id), &version_state, &version_value)?;
+    save_value(deps.branch(), &env, Uint64::new(version_id), version_value)?;
+    let version_link = Deeplink {
+        type_: "PackageVersion".into(),
+        from: Some(package_state.id.into()),
+        to: Some(version_id.into()),
+    };
+    let version_link_id = create_deeplink(deps.branch(), &env, &info.sender, version_link, None)?;
+    let version_link_value = Value::String(version.clone());
+    let version_link_state = deeplinks().load(deps.storage, version_link_id)?;
+    check_value_kind(deps.as_ref(), Uint64::new(version_link_id), &version_link_state, &version_link_value)?;
+    save_value(deps.branch(), &env, Uint64::new(version_link_id), version_link_value)?;
+    PACKAGE_VERSIONS.save(deps.storage, (package.as_str(), version.as_str()), &PackageVersionState {
+        id: version_id,
+        dependencies,
+    })?;
+    activate_package_version(deps.branch(), &env, &info.sender, &package, &version)?;
+
+    let data = InstallPackageResponse {
+        package: package.clone(),
+        version: version.clone(),
+        ids: ids.into_iter().map(Uint64::new).collect(),
+    };
+    Ok(Response::new()
+        .set_data(to_json_binary(&data)?)
+        .add_attributes(vec![
+            attr("action", "install_package"),
+            attr("package", package),
+            attr("version", version),
+        ])
+    )
 }
 
-pub fn execute_update_deeplink(
-    deps: DepsMut,
+pub fn execute_uninstall_package(
+    mut deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    type_: String,
-    from: Option<String>,
-    to: Option<String>,
+    package: String,
+    mode: Option<DeleteMode>,
 ) -> Result<Response, ContractError> {
+    let package_state = PACKAGES.may_load(deps.storage, package.as_str())?
+        .ok_or_else(|| ContractError::PackageNotExists { package: package.clone() })?;
+    let cfg = CONFIG.load(deps.storage)?;
+    if !cfg.can_modify(info.sender.as_ref()) && package_state.publisher != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let mode = mode.unwrap_or(DeleteMode::Restrict);
+
+    let dependents = PACKAGE_DEPENDENTS
+        .prefix(package.as_str())
+        .keys(deps.storage, None, None, Order::Ascending)
+        .filter(|dependent| !matches!(dependent, Ok(dependent) if *dependent == package))
+        .collect::<StdResult<Vec<_>>>()?;
+    if mode == DeleteMode::Restrict && !dependents.is_empty() {
+        return Err(ContractError::PackageHasDependents { package, dependents });
+    }
+
+    // Installed deeplinks of all versions, the namespace and the version links
+    let entries = PACKAGE_IDS
+        .prefix(package.as_str())
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let versions = PACKAGE_VERSIONS
+        .prefix(package.as_str())
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut owned = BTreeSet::from([package_state.id]);
+    owned.extend(entries.iter().copied());
+    owned.extend(versions.iter().map(|(_, version_state)| version_state.id));
+    for name in ["PackageVersion", "PackageActive"] {
+        if let Some(type_) = NAMED_DEEPLINKS.may_load(deps.storage, name)? {
+            let links = deeplinks().idx.type_from.prefix((type_, package_state.id));
+            for link_id in links.keys(deps.storage, None, None, Order::Ascending) {
+                owned.insert(link_id?);
+            }
+        }
+    }
+    let live = owned.iter()
+        .copied()
+        .filter(|id| !DELETED_IDS.has(deps.storage, *id))
+        .collect::<BTreeSet<_>>();
+
+    let mut affected = live.clone();
+    let mut outside = BTreeSet::new();
+    let mut queue = live.iter().copied().collect::<Vec<_>>();
+    while let Some(next) = queue.pop() {
+        for referrer in referrers(deps.as_ref(), next)? {
+            if affected.contains(&referrer) {
+                continue;
+            }
+            match mode {
+                DeleteMode::Restrict => {
+                    outside.insert(referrer);
+                }
+                DeleteMode::Cascade => {
+                    affected.insert(referrer);
+                    queue.push(referrer);
+                }
+            }
+        }
+    }
+    if !outside.is_empty() {
+        return Err(ContractError::PackageHasReferrers {
+            package,
+            referrers: outside.into_iter().map(Uint64::new).collect(),
+        });
+    }
+
+    // The package grants its own deeplinks, cascaded ones need the usual permission
+    for id in affected.difference(&live) {
+        if !cfg.can_modify_deeplink(info.sender.as_ref(), &deeplinks().load(deps.storage, *id)?) {
+            return Err(ContractError::Unauthorized {});
+        }
+    }
+    for id in affected.iter() {
+        check_not_core(deps.storage, *id)?;
+    }
+    for id in affected.iter() {
+        DELETED_IDS.save(deps.storage, *id, &true)?;
+    }
 
-    Ok(Response::new().add_attributes(vec![attr("action", "update")]))
+    // Names and records of the package are freed, so it can be installed again
+    for id in owned {
+        remove_names(deps.branch(), id)?;
+    }
+    for id in entries {
+        PACKAGE_IDS.remove(deps.storage, (package.as_str(), id));
+        DEEPLINK_PACKAGES.remove(deps.storage, id);
+    }
+    for (version, version_state) in versions {
+        for dependency in version_state.dependencies {
+            PACKAGE_DEPENDENTS.remove(deps.storage, (dependency.package.as_str(), package.as_str()));
+        }
+        PACKAGE_VERSIONS.remove(deps.storage, (package.as_str(), version.as_str()));
+    }
+    for dependent in dependents {
+        PACKAGE_DEPENDENTS.remove(deps.storage, (package.as_str(), dependent.as_str()));
+    }
+    PACKAGES.remove(deps.storage, package.as_str());
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "uninstall_package"),
+            attr("package", package),
+            attr("affected_ids", join_ids(&affected)),
+        ])
+    )
 }
 
-pub fn execute_delete_deeplink(
+pub fn execute_activate_package_version(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
-    id: Uint64
+    package: String,
+    version: String,
 ) -> Result<Response, ContractError> {
+    let package_state = PACKAGES.may_load(deps.storage, package.as_str())?
+        .ok_or_else(|| ContractError::PackageNotExists { package: package.clone() })?;
     let cfg = CONFIG.load(deps.storage)?;
-    if !cfg.can_modify(info.sender.as_ref()) {
+    if !cfg.can_modify(info.sender.as_ref()) && package_state.publisher != info.sender {
         return Err(ContractError::Unauthorized {});
     }
+    let version_state = PACKAGE_VERSIONS.may_load(deps.storage, (package.as_str(), version.as_str()))?
+        .ok_or_else(|| ContractError::PackageVersionNotExists { package: package.clone(), version: version.clone() })?;
 
-    // Mark the deeplink as deleted
-    DELETED_IDS.save(deps.storage, id.u64(), &true)?;
+    check_dependencies(deps.as_ref(), &package, &version, &version_state.dependencies)?;
+    activate_package_version(deps, &env, &info.sender, &package, &version)?;
 
     Ok(Response::new()
         .add_attributes(vec![
-            attr("action", "delete_deeplink"),
-            attr("id", id.to_string())
+            attr("action", "activate_package_version"),
+            attr("package", package),
+            attr("version", version),
         ])
     )
 }
 
-pub fn execute_update_admins(
+pub fn execute_update_creation_policy(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    new_admins: Vec<String>,
+    policy: CreationPolicyMsg,
 ) -> Result<Response, ContractError> {
     let cfg = CONFIG.load(deps.storage)?;
     if !cfg.can_modify(info.sender.as_ref()) {
         return Err(ContractError::Unauthorized {});
     }
 
-    let admins = map_validate(deps.api, &new_admins)?;
+    let creation_policy = match policy {
+        CreationPolicyMsg::Open => CreationPolicy::Open,
+        CreationPolicyMsg::AdminsOnly => CreationPolicy::AdminsOnly,
+        CreationPolicyMsg::AllowList { creators } => CreationPolicy::AllowList {
+            creators: map_validate(deps.api, &creators)?,
+        },
+        CreationPolicyMsg::PerType { types } => CreationPolicy::PerType {
+            types: types
+                .into_iter()
+                .map(|t| {
+                    let (type_, _) = resolve_deeplink(deps.as_ref(), &t.type_, None)?
+                        .ok_or_else(|| ContractError::TypeNotExists { type_: t.type_.to_string() })?;
+                    Ok(TypeAllowList {
+                        type_,
+                        creators: map_validate(deps.api, &t.creators)?,
+                    })
+                })
+                .collect::<Result<Vec<TypeAllowList>, ContractError>>()?,
+        },
+    };
     CONFIG.update(deps.storage, |mut cfg| -> StdResult<_> {
-        cfg.admins = admins;
+        cfg.creation_policy = creation_policy;
         Ok(cfg)
     })?;
 
-    Ok(Response::new().add_attributes(vec![attr("action", "update_admins")]))
+    Ok(Response::new().add_attributes(vec![attr("action", "update_creation_policy")]))
 }
 
-pub fn execute_update_executors(
+pub fn execute_update_max_object_size(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    new_executors: Vec<String>,
+    max_object_size: u32,
 ) -> Result<Response, ContractError> {
     let cfg = CONFIG.load(deps.storage)?;
     if !cfg.can_modify(info.sender.as_ref()) {
         return Err(ContractError::Unauthorized {});
     }
 
-    let executors = map_validate(deps.api, &new_executors)?;
+    // Objects stored under a bigger limit are kept
     CONFIG.update(deps.storage, |mut cfg| -> StdResult<_> {
-        cfg.executors = executors;
+        cfg.max_object_size = max_object_size;
         Ok(cfg)
     })?;
 
-    Ok(Response::new().add_attributes(vec![attr("action", "update_executors")]))
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "update_max_object_size"),
+            attr("max_object_size", max_object_size.to_string())
+        ])
+    )
 }
 
 pub fn execute_cyberlink(
@@ -244,3 +1773,6 @@ pub fn execute_cyberlink(
     Ok(Response::new().add_submessage(SubMsg::reply_on_error(msg, CYBERLINK_ID_MSG)))
 }
 
+fn join_ids(ids: &BTreeSet<u64>) -> String {
+    ids.iter().map(|id| id.to_string()).collect::<Vec<String>>().join(",")
+}
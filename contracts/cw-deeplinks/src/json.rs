@@ -0,0 +1,365 @@
+// Object values are kept as JSON text, floats can't be parsed inside the contract,
+// so this only checks the syntax and slices nested fields out of the text
+
+const MAX_DEPTH: usize = 64;
+
+struct Scanner<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(src: &'a str) -> Self {
+        Scanner { src, pos: 0 }
+    }
+
+    fn error(&self, msg: &str) -> String {
+        format!("{} at position {}", msg, self.pos)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.as_bytes().get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek();
+        if byte.is_some() {
+            self.pos += 1;
+        }
+        byte
+    }
+
+    fn skip_ws(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\n' | b'\r' | b'\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        self.skip_ws();
+        if self.peek() != Some(byte) {
+            return Err(self.error(&format!("expected '{}'", byte as char)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn value(&mut self, depth: usize) -> Result<(), String> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nesting is too deep"));
+        }
+        self.skip_ws();
+        match self.peek() {
+            Some(b'{') => self.object(depth),
+            Some(b'[') => self.array(depth),
+            Some(b'"') => self.string().map(|_| ()),
+            Some(b't') => self.literal("true"),
+            Some(b'f') => self.literal("false"),
+            Some(b'n') => self.literal("null"),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end")),
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<(), String> {
+        self.expect(b'{')?;
+        self.skip_ws();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(());
+        }
+        loop {
+            self.skip_ws();
+            self.string()?;
+            self.expect(b':')?;
+            self.value(depth + 1)?;
+            if !self.separator(b'}')? {
+                return Ok(());
+            }
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<(), String> {
+        self.expect(b'[')?;
+        self.skip_ws();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(());
+        }
+        loop {
+            self.value(depth + 1)?;
+            if !self.separator(b']')? {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Consumes ',' or the closing byte, returns whether more items follow
+    fn separator(&mut self, close: u8) -> Result<bool, String> {
+        self.skip_ws();
+        match self.peek() {
+            Some(b',') => {
+                self.pos += 1;
+                Ok(true)
+            }
+            Some(byte) if byte == close => {
+                self.pos += 1;
+                Ok(false)
+            }
+            _ => Err(self.error(&format!("expected ',' or '{}'", close as char))),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if self.peek() != Some(b'"') {
+            return Err(self.error("expected string"));
+        }
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let start = self.pos;
+            while matches!(self.peek(), Some(byte) if byte != b'"' && byte != b'\\' && byte >= 0x20) {
+                self.pos += 1;
+            }
+            out.push_str(&self.src[start..self.pos]);
+            match self.next() {
+                Some(b'"') => return Ok(out),
+                Some(b'\\') => out.push(self.escape()?),
+                Some(_) => {
+                    self.pos -= 1;
+                    return Err(self.error("control character in string"));
+                }
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, String> {
+        match self.next() {
+            Some(b'"') => Ok('"'),
+            Some(b'\\') => Ok('\\'),
+            Some(b'/') => Ok('/'),
+            Some(b'b') => Ok('\u{8}'),
+            Some(b'f') => Ok('\u{c}'),
+            Some(b'n') => Ok('\n'),
+            Some(b'r') => Ok('\r'),
+            Some(b't') => Ok('\t'),
+            Some(b'u') => {
+                let high = self.hex4()?;
+                let code = if (0xD800..0xDC00).contains(&high) {
+                    if self.next() != Some(b'\\') || self.next() != Some(b'u') {
+                        return Err(self.error("unpaired surrogate"));
+                    }
+                    let low = self.hex4()?;
+                    if !(0xDC00..0xE000).contains(&low) {
+                        return Err(self.error("unpaired surrogate"));
+                    }
+                    0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                } else {
+                    high
+                };
+                char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))
+            }
+            _ => Err(self.error("invalid escape")),
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self.src.get(self.pos..self.pos + 4)
+            .filter(|digits| digits.bytes().all(|byte| byte.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        let code = u32::from_str_radix(digits, 16)
+            .map_err(|_| self.error("invalid unicode escape"))?;
+        self.pos += 4;
+        Ok(code)
+    }
+
+    fn digits(&mut self) -> usize {
+        let start = self.pos;
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+
+    fn number(&mut self) -> Result<(), String> {
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        match self.peek() {
+            Some(b'0') => self.pos += 1,
+            Some(b'1'..=b'9') => {
+                self.digits();
+            }
+            _ => return Err(self.error("invalid number")),
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            if self.digits() == 0 {
+                return Err(self.error("invalid number"));
+            }
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.pos += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            if self.digits() == 0 {
+                return Err(self.error("invalid number"));
+            }
+        }
+        Ok(())
+    }
+
+    fn literal(&mut self, literal: &str) -> Result<(), String> {
+        if !self.src[self.pos..].starts_with(literal) {
+            return Err(self.error("unexpected literal"));
+        }
+        self.pos += literal.len();
+        Ok(())
+    }
+
+    fn end(&mut self) -> Result<(), String> {
+        self.skip_ws();
+        if self.pos != self.src.len() {
+            return Err(self.error("trailing characters"));
+        }
+        Ok(())
+    }
+}
+
+/// Checks that the text is a single well formed JSON object
+pub fn validate_object(src: &str) -> Result<(), String> {
+    let mut scanner = Scanner::new(src);
+    scanner.skip_ws();
+    if scanner.peek() != Some(b'{') {
+        return Err(scanner.error("expected object"));
+    }
+    scanner.value(0)?;
+    scanner.end()
+}
+
+/// Returns the JSON text of the field at a dot separated path, like `result.items.0.name`,
+/// numeric segments index arrays and an empty path selects the whole document
+pub fn select<'a>(src: &'a str, path: &str) -> Result<Option<&'a str>, String> {
+    let mut scanner = Scanner::new(src);
+    let segments = path.split('.').filter(|segment| !segment.is_empty());
+    for segment in segments {
+        scanner.skip_ws();
+        let found = match scanner.peek() {
+            Some(b'{') => select_member(&mut scanner, segment)?,
+            Some(b'[') => match segment.parse::<usize>() {
+                Ok(index) => select_element(&mut scanner, index)?,
+                Err(_) => false,
+            },
+            _ => false,
+        };
+        if !found {
+            return Ok(None);
+        }
+    }
+    scanner.skip_ws();
+    let start = scanner.pos;
+    scanner.value(0)?;
+    Ok(Some(&src[start..scanner.pos]))
+}
+
+fn select_member(scanner: &mut Scanner, key: &str) -> Result<bool, String> {
+    scanner.expect(b'{')?;
+    scanner.skip_ws();
+    if scanner.peek() == Some(b'}') {
+        return Ok(false);
+    }
+    loop {
+        scanner.skip_ws();
+        let member = scanner.string()?;
+        scanner.expect(b':')?;
+        if member == key {
+            return Ok(true);
+        }
+        scanner.value(1)?;
+        if !scanner.separator(b'}')? {
+            return Ok(false);
+        }
+    }
+}
+
+fn select_element(scanner: &mut Scanner, index: usize) -> Result<bool, String> {
+    scanner.expect(b'[')?;
+    scanner.skip_ws();
+    if scanner.peek() == Some(b']') {
+        return Ok(false);
+    }
+    for _ in 0..index {
+        scanner.value(1)?;
+        if !scanner.separator(b']')? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{select, validate_object, MAX_DEPTH};
+
+    #[test]
+    fn test_validate_object() {
+        assert_eq!(validate_object(r#" {"a": [1, -2.5e3, true, null], "b": {"c": "d"}} "#), Ok(()));
+        assert_eq!(validate_object("{}"), Ok(()));
+
+        let invalid = [
+            ("[1, 2]", "expected object at position 0"),
+            (r#"{"a": 1,}"#, "expected string at position 8"),
+            (r#"{"a": [1,]}"#, "unexpected character at position 9"),
+            (r#"{"a": 01}"#, "expected ',' or '}' at position 7"),
+            (r#"{"a": 1.}"#, "invalid number at position 8"),
+            (r#"{"a": -}"#, "invalid number at position 7"),
+            (r#"{"a": "\x"}"#, "invalid escape at position 9"),
+            (r#"{"a": "\u12g4"}"#, "invalid unicode escape at position 9"),
+            (r#"{"a": "\ud800"}"#, "unpaired surrogate at position 14"),
+            (r#"{"a": "\ud800\u0041"}"#, "unpaired surrogate at position 19"),
+            (r#"{"a": "\udc00"}"#, "invalid unicode escape at position 13"),
+            ("{\"a\": \"line\nbreak\"}", "control character in string at position 11"),
+            (r#"{"a": "open}"#, "unterminated string at position 12"),
+            (r#"{"a": tru}"#, "unexpected literal at position 6"),
+            (r#"{"a": 1} {}"#, "trailing characters at position 9"),
+        ];
+        for (src, error) in invalid.iter() {
+            assert_eq!(validate_object(src), Err(error.to_string()), "{}", src);
+        }
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let nested = |depth: usize| format!(r#"{{"a": {}{}}}"#, "[".repeat(depth), "]".repeat(depth));
+        assert_eq!(validate_object(&nested(MAX_DEPTH)), Ok(()));
+        let err = validate_object(&nested(MAX_DEPTH + 1)).unwrap_err();
+        assert!(err.starts_with("nesting is too deep"), "{}", err);
+    }
+
+    #[test]
+    fn test_non_ascii() {
+        let src = r#"{"имя": "значение", "café": "☕", "emoji": "😀"}"#;
+        assert_eq!(validate_object(src), Ok(()));
+        assert_eq!(select(src, "имя"), Ok(Some(r#""значение""#)));
+        assert_eq!(select(src, "café"), Ok(Some(r#""☕""#)));
+        assert_eq!(select(src, "emoji"), Ok(Some(r#""😀""#)));
+    }
+
+    #[test]
+    fn test_select() {
+        let src = r#" {"result": {"items": [{"name": "a"}, {"name": "b"}], "count": 2}} "#;
+        assert_eq!(select(src, "result.items.1.name"), Ok(Some(r#""b""#)));
+        assert_eq!(select(src, "result.count"), Ok(Some("2")));
+        assert_eq!(select(src, ""), Ok(Some(src.trim())));
+
+        assert_eq!(select(src, "result.missing"), Ok(None));
+        assert_eq!(select(src, "result.items.2"), Ok(None));
+        assert_eq!(select(src, "result.items.name"), Ok(None));
+        assert_eq!(select(src, "result.count.value"), Ok(None));
+        assert_eq!(select("[]", "0"), Ok(None));
+        assert_eq!(select("{}", "a"), Ok(None));
+    }
+}
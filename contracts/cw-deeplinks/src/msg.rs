@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+use std::fmt;
 use cosmwasm_std::Uint64;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -9,6 +11,13 @@ pub struct InstantiateMsg {
     pub executers: Vec<String>,
 }
 
+/// Core deeplinks can only be changed here, entries are created or replaced in place by name
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct MigrateMsg {
+    #[serde(default)]
+    pub core_deeplinks: Vec<NamedDeeplink>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct NamedDeeplink {
     pub id: String,
@@ -16,37 +25,279 @@ pub struct NamedDeeplink {
     pub type_: String,
     pub from: Option<String>,
     pub to: Option<String>,
+    pub value: Option<ManifestValue>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PackageDependency {
+    pub package: String,
+    /// Semver requirement like `^1.2`, matched against the active version
+    pub version: String,
+}
+
+/// Value of a manifest entry as in core.json, `{ "value": ... }`, strings are
+/// taken as JSON text when the type declares Object values
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ManifestValue {
+    pub value: ManifestValueContent,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(untagged)]
+pub enum ManifestValueContent {
+    Number(u64),
+    String(String),
+}
+
+impl From<Value> for ManifestValue {
+    fn from(value: Value) -> Self {
+        let value = match value {
+            Value::Number(number) => ManifestValueContent::Number(number.u64()),
+            Value::String(string) | Value::Object(string) => ManifestValueContent::String(string),
+        };
+        ManifestValue { value }
+    }
+}
+
+/// Reference to an existing deeplink, either by its numeric id or by its name
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeeplinkRef {
+    Id(Uint64),
+    Name(String),
+}
+
+impl fmt::Display for DeeplinkRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeeplinkRef::Id(id) => write!(f, "{}", id),
+            DeeplinkRef::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl From<&str> for DeeplinkRef {
+    fn from(name: &str) -> Self {
+        DeeplinkRef::Name(name.to_string())
+    }
+}
+
+impl From<u64> for DeeplinkRef {
+    fn from(id: u64) -> Self {
+        DeeplinkRef::Id(Uint64::new(id))
+    }
 }
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct  Deeplink {
     #[serde(rename = "type")]
-    pub type_: String,
-    pub from: Option<String>,
-    pub to: Option<String>,
+    pub type_: DeeplinkRef,
+    pub from: Option<DeeplinkRef>,
+    pub to: Option<DeeplinkRef>,
+}
+
+impl From<NamedDeeplink> for Deeplink {
+    fn from(deeplink: NamedDeeplink) -> Self {
+        Deeplink {
+            type_: DeeplinkRef::Name(deeplink.type_),
+            from: deeplink.from.map(DeeplinkRef::Name),
+            to: deeplink.to.map(DeeplinkRef::Name),
+        }
+    }
+}
+
+/// Whether instances of a type must, may or can't have an end
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EndRule {
+    Required,
+    Forbidden,
+    #[default]
+    Optional,
+}
+
+/// End rules a type declares for its instances, undeclared ends are optional
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema, Default)]
+pub struct TypeEnds {
+    #[serde(default)]
+    pub from: EndRule,
+    #[serde(default)]
+    pub to: EndRule,
+}
+
+/// How a delete treats deeplinks that still reference the deleted one
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeleteMode {
+    /// Fail if any live deeplink uses it as type, from or to
+    Restrict,
+    /// Delete all dependent deeplinks transitively
+    Cascade,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Value {
+    Number(Uint64),
+    String(String),
+    /// JSON object as text
+    Object(String),
+}
+
+impl Value {
+    /// Name of the core type the value belongs to
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Value::Number(_) => "Number",
+            Value::String(_) => "String",
+            Value::Object(_) => "Object",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CreationPolicyMsg {
+    Open,
+    AdminsOnly,
+    AllowList { creators: Vec<String> },
+    PerType { types: Vec<TypeAllowListMsg> },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TypeAllowListMsg {
+    #[serde(rename = "type")]
+    pub type_: DeeplinkRef,
+    pub creators: Vec<String>,
+}
+
+/// Operation of a Batch. References by name starting with `$` point to deeplinks created
+/// earlier in the batch, `$<index>` by the index of the operation or `$<placeholder>`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchOp {
+    Create {
+        deeplink: Deeplink,
+        placeholder: Option<String>,
+    },
+    Update {
+        id: DeeplinkRef,
+        deeplink: Deeplink,
+    },
+    Delete {
+        id: DeeplinkRef,
+        mode: Option<DeleteMode>,
+    },
+    SetValue {
+        id: DeeplinkRef,
+        value: Value,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
+    /// Plain names are put into the package, if one is given. An existing name
+    /// fails unless `upsert` is set, core names can't be replaced this way
     CreatedNamedDeeplink {
         name: String,
         deeplink: Deeplink,
+        package: Option<String>,
+        #[serde(default)]
+        upsert: bool,
     },
+    /// Names without a package are looked up in the given package first
     CreateDeeplink {
         deeplink: Deeplink,
+        package: Option<String>,
     },
+    /// Errors name the index of the failed entry. With `continue_on_error` invalid
+    /// entries are skipped and listed in the data instead
     CreateDeeplinks {
         deeplinks: Vec<Deeplink>,
+        package: Option<String>,
+        #[serde(default)]
+        continue_on_error: bool,
+    },
+    /// Applies the operations in order as one unit, nothing is written if one fails
+    Batch {
+        ops: Vec<BatchOp>,
+        package: Option<String>,
     },
-    // TODO update UpdateDeeplink
     UpdateDeeplink {
+        id: Uint64,
+        deeplink: Deeplink,
+        package: Option<String>,
+    },
+    /// Declares which ends instances of the type must or can't have
+    SetTypeEnds {
         #[serde(rename = "type")]
-        type_: String,
-        from: Option<String>,
-        to: Option<String>,
+        type_: DeeplinkRef,
+        ends: TypeEnds,
+    },
+    /// Moves a name or alias to a new key, references by id are not affected
+    RenameDeeplink {
+        name: String,
+        new_name: String,
+    },
+    /// Adds a name resolving to the deeplink, the first name of a deeplink is canonical
+    AddAlias {
+        id: Uint64,
+        alias: String,
+    },
+    RemoveAlias {
+        alias: String,
+    },
+    /// Makes an alias canonical, the previous canonical name becomes an alias
+    SetCanonicalName {
+        name: String,
+    },
+    /// Registers a package namespace published by the sender
+    CreatePackage {
+        name: String,
+    },
+    /// Creates all manifest entries under the package or nothing, entries may
+    /// reference each other in any order. The new version becomes active.
+    InstallPackage {
+        package: String,
+        version: String,
+        deeplinks: Vec<NamedDeeplink>,
+        #[serde(default)]
+        dependencies: Vec<PackageDependency>,
+    },
+    /// Deletes all deeplinks of the package and frees its names. Restrict fails if other
+    /// packages depend on it or other deeplinks reference it, cascade deletes those too
+    UninstallPackage {
+        package: String,
+        mode: Option<DeleteMode>,
+    },
+    /// Points the package names to the deeplinks of an installed version
+    ActivatePackageVersion {
+        package: String,
+        version: String,
     },
     DeleteDeeplink {
         id: Uint64,
+        mode: Option<DeleteMode>,
+    },
+    RestoreDeeplink {
+        id: Uint64,
+    },
+    PurgeDeeplinks {
+        limit: Option<u32>,
+    },
+    /// Fails if the deeplink already has a value
+    SetValue {
+        id: Uint64,
+        value: Value,
+    },
+    /// Replaces an existing value, possibly with one of another kind
+    UpdateValue {
+        id: Uint64,
+        value: Value,
+    },
+    ClearValue {
+        id: Uint64,
     },
     Cyberlink {
         links: Vec<Link>
@@ -57,15 +308,181 @@ pub enum ExecuteMsg {
     UpdateExecutors {
         new_executors: Vec<String>
     },
+    UpdateCreationPolicy {
+        policy: CreationPolicyMsg
+    },
+    UpdateMaxObjectSize {
+        max_object_size: u32
+    },
+}
+
+/// Data of InstallPackage
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstallPackageResponse {
+    pub package: String,
+    pub version: String,
+    /// Ids of the created deeplinks in manifest order
+    pub ids: Vec<Uint64>,
+}
+
+/// Data of CreateDeeplink, CreateDeeplinks and CreatedNamedDeeplink
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CreateDeeplinksResponse {
+    /// Created deeplinks in message order
+    pub deeplinks: Vec<CreatedDeeplink>,
+    /// Entries skipped by `continue_on_error`
+    #[serde(default)]
+    pub failures: Vec<EntryFailure>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EntryFailure {
+    /// Position of the entry in the message
+    pub index: u32,
+    pub error: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CreatedDeeplink {
+    pub id: Uint64,
+    pub name: Option<String>,
+}
+
+/// Data of Batch
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BatchResponse {
+    /// Ids of the created deeplinks by placeholder, both `$<index>` and `$<placeholder>`
+    pub placeholders: BTreeMap<String, Uint64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     LastId {},
-    DebugState {},
+    ListDeeplinks {
+        start_after: Option<Uint64>,
+        limit: Option<u32>,
+    },
+    ListNamed {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    ListDeleted {
+        start_after: Option<Uint64>,
+        limit: Option<u32>,
+    },
+    DumpState {
+        start_after: Option<Uint64>,
+        limit: Option<u32>,
+    },
     Deeplink {
         id: Uint64,
     },
+    Package {
+        name: String,
+    },
+    /// Live deeplinks of the package as a manifest, fails when there are more than `limit`
+    ExportPackage {
+        package: String,
+        limit: Option<u32>,
+    },
+    /// Root and everything reachable from it through Contain links as a manifest,
+    /// fails when there are more than `limit`
+    ExportSubgraph {
+        root: DeeplinkRef,
+        limit: Option<u32>,
+    },
+    PackageVersions {
+        package: String,
+    },
+    PackageIds {
+        package: String,
+        start_after: Option<Uint64>,
+        limit: Option<u32>,
+    },
+    ResolveName {
+        name: String,
+    },
+    Names {
+        id: Uint64,
+    },
+    ByType {
+        #[serde(rename = "type")]
+        type_: DeeplinkRef,
+        start_after: Option<Uint64>,
+        limit: Option<u32>,
+    },
+    ByFrom {
+        from: DeeplinkRef,
+        start_after: Option<Uint64>,
+        limit: Option<u32>,
+    },
+    ByTo {
+        to: DeeplinkRef,
+        start_after: Option<Uint64>,
+        limit: Option<u32>,
+    },
+    ByTypeFrom {
+        #[serde(rename = "type")]
+        type_: DeeplinkRef,
+        from: DeeplinkRef,
+        start_after: Option<Uint64>,
+        limit: Option<u32>,
+    },
+    ByTypeTo {
+        #[serde(rename = "type")]
+        type_: DeeplinkRef,
+        to: DeeplinkRef,
+        start_after: Option<Uint64>,
+        limit: Option<u32>,
+    },
+    ByFromTo {
+        from: DeeplinkRef,
+        to: DeeplinkRef,
+        start_after: Option<Uint64>,
+        limit: Option<u32>,
+    },
+    ByCreator {
+        creator: String,
+        start_after: Option<Uint64>,
+        limit: Option<u32>,
+    },
+    TypeEnds {
+        #[serde(rename = "type")]
+        type_: DeeplinkRef,
+    },
+    /// Whether the type of the deeplink is the given type or extends it
+    IsInstanceOf {
+        id: Uint64,
+        #[serde(rename = "type")]
+        type_: DeeplinkRef,
+    },
+    /// Deeplinks of the type, with `include_subtypes` also of the types extending it
+    InstancesOf {
+        #[serde(rename = "type")]
+        type_: DeeplinkRef,
+        #[serde(default)]
+        include_subtypes: bool,
+        start_after: Option<Uint64>,
+        limit: Option<u32>,
+    },
+    /// Dry run of CreateDeeplinks, reports every problem of each deeplink and,
+    /// when the sender is given, whether it may create them
+    ValidateDeeplinks {
+        deeplinks: Vec<Deeplink>,
+        package: Option<String>,
+        sender: Option<String>,
+    },
+    Value {
+        id: Uint64,
+    },
+    DeeplinkWithValue {
+        id: Uint64,
+    },
+    /// Field of an object value at a dot separated path, numeric segments index arrays
+    ValueAtPath {
+        id: Uint64,
+        path: String,
+    },
     Config {},
 }
@@ -1,9 +1,17 @@
-use cosmwasm_std::{Deps, StdError, StdResult, Uint64};
-use crate::state::{CONFIG, DEEPLINKS, DeeplinkState, DELETED_IDS, ID, NAMED_DEEPLINKS};
+use std::collections::{BTreeMap, BTreeSet};
+use cosmwasm_std::{Deps, Order, StdError, StdResult, Storage, Uint64};
+use cw_storage_plus::{Bound, Prefix};
+use crate::state::{CONFIG, CreationPolicy, deeplinks, DeeplinkState, DELETED_IDS, ID, NAMED_DEEPLINKS, DEEPLINK_NAMES, NUMBERS_VALUES, STRINGS_VALUES, OBJECTS_VALUES, PACKAGES, PACKAGE_IDS, PACKAGE_VERSIONS, split_name, TYPE_ENDS, is_subtype, names_of, subtypes, type_parents};
 use serde::{Deserialize, Serialize};
 use schemars::JsonSchema;
-use crate::ContractError;
-use crate::msg::Deeplink;
+use semver::Version;
+use crate::msg::{Deeplink, DeeplinkRef, NamedDeeplink, PackageDependency, TypeEnds, Value};
+use crate::execute::{diagnose_deeplink, manifest_order};
+use crate::json::select;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 100;
+const MAX_EXPORT_LIMIT: u32 = 1000;
 
 pub fn query_last_id(deps: Deps) -> StdResult<Uint64> {
     let last_id = ID.load(deps.storage)?;
@@ -17,15 +25,436 @@ pub fn query_id(deps: Deps, id: Uint64) -> StdResult<DeeplinkState> {
     }
 
     // Load the deeplink state
-    let deeplink = DEEPLINKS.load(deps.storage, id.u64())?;
+    let deeplink = deeplinks().load(deps.storage, id.u64())?;
     Ok(deeplink)
 }
 
+pub(crate) fn load_value(storage: &dyn Storage, id: u64) -> StdResult<Option<Value>> {
+    if let Some((_, number)) = NUMBERS_VALUES.may_load(storage, id)? {
+        return Ok(Some(Value::Number(Uint64::new(number))));
+    }
+    if let Some((_, string)) = STRINGS_VALUES.may_load(storage, id)? {
+        return Ok(Some(Value::String(string)));
+    }
+    Ok(OBJECTS_VALUES.may_load(storage, id)?.map(|(_, object)| Value::Object(object)))
+}
+
+pub fn query_value(deps: Deps, id: Uint64) -> StdResult<ValueResponse> {
+    query_id(deps, id)?;
+    Ok(ValueResponse {
+        id,
+        value: load_value(deps.storage, id.u64())?,
+    })
+}
+
+pub fn query_deeplink_with_value(deps: Deps, id: Uint64) -> StdResult<DeeplinkWithValueResponse> {
+    Ok(DeeplinkWithValueResponse {
+        id,
+        deeplink: query_id(deps, id)?,
+        value: load_value(deps.storage, id.u64())?,
+    })
+}
+
+pub fn query_value_at_path(deps: Deps, id: Uint64, path: String) -> StdResult<ValueAtPathResponse> {
+    query_id(deps, id)?;
+    let (_, object) = OBJECTS_VALUES.may_load(deps.storage, id.u64())?
+        .ok_or_else(|| StdError::not_found("object value"))?;
+    let value = select(&object, &path)
+        .map_err(StdError::generic_err)?
+        .map(|field| field.to_string());
+    Ok(ValueAtPathResponse { id, path, value })
+}
+
+pub fn query_package(deps: Deps, name: String) -> StdResult<PackageResponse> {
+    let package = PACKAGES.load(deps.storage, name.as_str())?;
+    Ok(PackageResponse {
+        name,
+        id: Uint64::new(package.id),
+        publisher: package.publisher.into(),
+        version: package.version,
+    })
+}
+
+pub fn query_package_versions(deps: Deps, package: String) -> StdResult<PackageVersionsResponse> {
+    let package_state = PACKAGES.load(deps.storage, package.as_str())?;
+    let mut versions = PACKAGE_VERSIONS
+        .prefix(package.as_str())
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(version, version_state)| PackageVersionResponse {
+            version,
+            id: Uint64::new(version_state.id),
+            dependencies: version_state.dependencies,
+        }))
+        .collect::<StdResult<Vec<_>>>()?;
+    // Stored versions are valid semver
+    versions.sort_by_cached_key(|version| Version::parse(&version.version).ok());
+
+    Ok(PackageVersionsResponse {
+        package,
+        active: package_state.version,
+        versions,
+    })
+}
+
+pub fn query_package_ids(deps: Deps, package: String, start_after: Option<Uint64>, limit: Option<u32>) -> StdResult<PackageIdsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|id| Bound::exclusive(id.u64()));
+
+    let ids = PACKAGE_IDS
+        .prefix(package.as_str())
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(Uint64::new))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(PackageIdsResponse { package, ids })
+}
+
+/// Entries keep the local part of their names or get `_<id>`, which is local
+/// to the manifest, references outside of the export are written with their full names
+fn export_manifest(deps: Deps, ids: BTreeSet<u64>) -> StdResult<Vec<NamedDeeplink>> {
+    let states = ids.iter()
+        .map(|id| Ok((*id, deeplinks().load(deps.storage, *id)?)))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    // A local symbol equal to an outside name would take its references on install
+    let mut outside = BTreeSet::new();
+    for (_, state) in states.iter() {
+        for id in [Some(state.type_), state.from, state.to].iter().flatten().filter(|id| !ids.contains(id)) {
+            outside.extend(DEEPLINK_NAMES.may_load(deps.storage, *id)?);
+        }
+    }
+
+    let mut symbols = BTreeMap::new();
+    let mut taken = BTreeSet::new();
+    for (id, _) in states.iter() {
+        let symbol = match DEEPLINK_NAMES.may_load(deps.storage, *id)? {
+            Some(name) => split_name(&name).map_or(name.clone(), |(_, local)| local.to_string()),
+            None => format!("_{}", id),
+        };
+        let symbol = if taken.contains(&symbol) || outside.contains(&symbol) { format!("_{}", id) } else { symbol };
+        taken.insert(symbol.clone());
+        symbols.insert(*id, symbol);
+    }
+    let symbol_of = |id: u64| -> StdResult<String> {
+        if let Some(symbol) = symbols.get(&id) {
+            return Ok(symbol.clone());
+        }
+        DEEPLINK_NAMES.may_load(deps.storage, id)?
+            .ok_or_else(|| StdError::generic_err(format!("Deeplink {} referenced by the export has no name", id)))
+    };
+
+    let positions = states.iter()
+        .enumerate()
+        .map(|(position, (id, _))| (*id, position))
+        .collect::<BTreeMap<_, _>>();
+    let references = states.iter()
+        .map(|(_, state)| [Some(state.type_), state.from, state.to]
+            .iter()
+            .filter_map(|id| id.and_then(|id| positions.get(&id).copied()))
+            .collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    manifest_order(&references)
+        .into_iter()
+        .map(|position| {
+            let (id, state) = &states[position];
+            Ok(NamedDeeplink {
+                id: symbol_of(*id)?,
+                type_: symbol_of(state.type_)?,
+                from: state.from.map(symbol_of).transpose()?,
+                to: state.to.map(symbol_of).transpose()?,
+                value: load_value(deps.storage, *id)?.map(Into::into),
+            })
+        })
+        .collect()
+}
+
+fn export_limit_error(limit: u32) -> StdError {
+    StdError::generic_err(format!("Export has more than {} deeplinks", limit))
+}
+
+pub fn query_export_package(deps: Deps, package: String, limit: Option<u32>) -> StdResult<ExportResponse> {
+    let limit = limit.unwrap_or(MAX_EXPORT_LIMIT).min(MAX_EXPORT_LIMIT);
+    let package_state = PACKAGES.load(deps.storage, package.as_str())?;
+    let version = package_state.version
+        .ok_or_else(|| StdError::not_found("active package version"))?;
+    let dependencies = PACKAGE_VERSIONS.load(deps.storage, (package.as_str(), version.as_str()))?.dependencies;
+
+    let mut ids = BTreeSet::new();
+    for item in PACKAGE_IDS.prefix(package.as_str()).range(deps.storage, None, None, Order::Ascending) {
+        let (id, entry) = item?;
+        if entry.version == version && !DELETED_IDS.has(deps.storage, id) {
+            ids.insert(id);
+            if ids.len() > limit as usize {
+                return Err(export_limit_error(limit));
+            }
+        }
+    }
+
+    Ok(ExportResponse {
+        package: Some(package),
+        version: Some(version),
+        dependencies,
+        deeplinks: export_manifest(deps, ids)?,
+    })
+}
+
+pub fn query_export_subgraph(deps: Deps, root: DeeplinkRef, limit: Option<u32>) -> StdResult<ExportResponse> {
+    let limit = limit.unwrap_or(MAX_EXPORT_LIMIT).min(MAX_EXPORT_LIMIT);
+    let root = resolve_ref(deps, root)?;
+    query_id(deps, Uint64::new(root))?;
+
+    let contain = NAMED_DEEPLINKS.load(deps.storage, "Contain")?;
+    let mut ids = BTreeSet::from([root]);
+    let mut queue = vec![root];
+    while let Some(id) = queue.pop() {
+        let links = deeplinks().idx.type_from.prefix((contain, id));
+        for item in links.range(deps.storage, None, None, Order::Ascending) {
+            let (link_id, link) = item?;
+            if DELETED_IDS.has(deps.storage, link_id) || matches!(link.to, Some(to) if DELETED_IDS.has(deps.storage, to)) {
+                continue;
+            }
+            ids.insert(link_id);
+            if let Some(to) = link.to.filter(|to| ids.insert(*to)) {
+                queue.push(to);
+            }
+            if ids.len() > limit as usize {
+                return Err(export_limit_error(limit));
+            }
+        }
+    }
+
+    Ok(ExportResponse {
+        package: None,
+        version: None,
+        dependencies: vec![],
+        deeplinks: export_manifest(deps, ids)?,
+    })
+}
+
+pub fn query_resolve_name(deps: Deps, name: String) -> StdResult<NameResponse> {
+    let id = NAMED_DEEPLINKS.load(deps.storage, name.as_str())?;
+    Ok(NameResponse {
+        name,
+        id: Uint64::new(id),
+    })
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct NameResponse {
+    pub name: String,
+    pub id: Uint64,
+}
+
+pub fn query_names(deps: Deps, id: Uint64) -> StdResult<NamesResponse> {
+    Ok(NamesResponse {
+        id,
+        canonical: DEEPLINK_NAMES.may_load(deps.storage, id.u64())?,
+        names: names_of(deps.storage, id.u64())?,
+    })
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct NamesResponse {
+    pub id: Uint64,
+    pub canonical: Option<String>,
+    /// Canonical name first, then the aliases
+    pub names: Vec<String>,
+}
+
+fn resolve_ref(deps: Deps, deeplink_ref: DeeplinkRef) -> StdResult<u64> {
+    match deeplink_ref {
+        DeeplinkRef::Id(id) => Ok(id.u64()),
+        DeeplinkRef::Name(name) => NAMED_DEEPLINKS.load(deps.storage, name.as_str()),
+    }
+}
+
+fn paginate_deeplinks(
+    deps: Deps,
+    prefix: Prefix<u64, DeeplinkState, u64>,
+    start_after: Option<Uint64>,
+    limit: Option<u32>,
+) -> StdResult<DeeplinksResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|id| Bound::exclusive(id.u64()));
+
+    let deeplinks = prefix
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter(|item| match item {
+            Ok((id, _)) => !DELETED_IDS.has(deps.storage, *id),
+            Err(_) => true,
+        })
+        .take(limit)
+        .map(|item| item.map(|(id, deeplink)| DeeplinkResponse { id: Uint64::new(id), deeplink }))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(DeeplinksResponse { deeplinks })
+}
+
+pub fn query_by_type(deps: Deps, type_: DeeplinkRef, start_after: Option<Uint64>, limit: Option<u32>) -> StdResult<DeeplinksResponse> {
+    let type_ = resolve_ref(deps, type_)?;
+    paginate_deeplinks(deps, deeplinks().idx.type_.prefix(type_), start_after, limit)
+}
+
+pub fn query_by_from(deps: Deps, from: DeeplinkRef, start_after: Option<Uint64>, limit: Option<u32>) -> StdResult<DeeplinksResponse> {
+    let from = resolve_ref(deps, from)?;
+    paginate_deeplinks(deps, deeplinks().idx.from.prefix(from), start_after, limit)
+}
+
+pub fn query_by_to(deps: Deps, to: DeeplinkRef, start_after: Option<Uint64>, limit: Option<u32>) -> StdResult<DeeplinksResponse> {
+    let to = resolve_ref(deps, to)?;
+    paginate_deeplinks(deps, deeplinks().idx.to.prefix(to), start_after, limit)
+}
+
+pub fn query_by_type_from(deps: Deps, type_: DeeplinkRef, from: DeeplinkRef, start_after: Option<Uint64>, limit: Option<u32>) -> StdResult<DeeplinksResponse> {
+    let (type_, from) = (resolve_ref(deps, type_)?, resolve_ref(deps, from)?);
+    paginate_deeplinks(deps, deeplinks().idx.type_from.prefix((type_, from)), start_after, limit)
+}
+
+pub fn query_by_type_to(deps: Deps, type_: DeeplinkRef, to: DeeplinkRef, start_after: Option<Uint64>, limit: Option<u32>) -> StdResult<DeeplinksResponse> {
+    let (type_, to) = (resolve_ref(deps, type_)?, resolve_ref(deps, to)?);
+    paginate_deeplinks(deps, deeplinks().idx.type_to.prefix((type_, to)), start_after, limit)
+}
+
+pub fn query_by_from_to(deps: Deps, from: DeeplinkRef, to: DeeplinkRef, start_after: Option<Uint64>, limit: Option<u32>) -> StdResult<DeeplinksResponse> {
+    let (from, to) = (resolve_ref(deps, from)?, resolve_ref(deps, to)?);
+    paginate_deeplinks(deps, deeplinks().idx.from_to.prefix((from, to)), start_after, limit)
+}
+
+pub fn query_by_creator(deps: Deps, creator: String, start_after: Option<Uint64>, limit: Option<u32>) -> StdResult<DeeplinksResponse> {
+    let creator = deps.api.addr_validate(&creator)?;
+    paginate_deeplinks(deps, deeplinks().idx.creator.prefix(creator), start_after, limit)
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct DeeplinkResponse {
+    pub id: Uint64,
+    pub deeplink: DeeplinkState,
+}
+
+pub fn query_type_ends(deps: Deps, type_: DeeplinkRef) -> StdResult<TypeEnds> {
+    let type_ = resolve_ref(deps, type_)?;
+    Ok(TYPE_ENDS.may_load(deps.storage, type_)?.unwrap_or_default())
+}
+
+pub fn query_is_instance_of(deps: Deps, id: Uint64, type_: DeeplinkRef) -> StdResult<IsInstanceOfResponse> {
+    let deeplink = query_id(deps, id)?;
+    let type_ = resolve_ref(deps, type_)?;
+    let instance = is_subtype(deeplink.type_, type_, &|parent| type_parents(deps.storage, parent))?;
+    Ok(IsInstanceOfResponse { id, type_: Uint64::new(type_), instance })
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct IsInstanceOfResponse {
+    pub id: Uint64,
+    #[serde(rename = "type")]
+    pub type_: Uint64,
+    pub instance: bool,
+}
+
+/// Checks the deeplinks as CreateDeeplinks would, without writing anything
+pub fn query_validate_deeplinks(
+    deps: Deps,
+    deeplinks: Vec<Deeplink>,
+    package: Option<String>,
+    sender: Option<String>,
+) -> StdResult<ValidateDeeplinksResponse> {
+    let sender = sender.map(|sender| deps.api.addr_validate(&sender)).transpose()?;
+    let results = deeplinks.iter().enumerate()
+        .map(|(index, deeplink)| {
+            let diagnostics = diagnose_deeplink(deps, deeplink, package.as_deref(), sender.as_ref())?;
+            Ok(DeeplinkValidation { index: index as u32, valid: diagnostics.is_empty(), diagnostics })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(ValidateDeeplinksResponse { results })
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ValidateDeeplinksResponse {
+    /// One result per deeplink in query order
+    pub results: Vec<DeeplinkValidation>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct DeeplinkValidation {
+    pub index: u32,
+    pub valid: bool,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Problem found in a deeplink, `end` is `type`, `from` or `to`
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Diagnostic {
+    /// The reference resolves to no deeplink
+    NotExists { end: String, reference: String },
+    Deleted { end: String, id: Uint64 },
+    EndRequired { end: String },
+    EndForbidden { end: String },
+    /// The type of the deeplink at the end is not the one the link type expects
+    TypeMismatch { end: String, expected: Uint64, received: Uint64 },
+    /// An Extends link from a type to itself or one of its subtypes
+    TypeCycle {},
+    /// The sender may not create deeplinks of the type
+    Unauthorized {},
+}
+
+pub fn query_instances_of(
+    deps: Deps,
+    type_: DeeplinkRef,
+    include_subtypes: bool,
+    start_after: Option<Uint64>,
+    limit: Option<u32>,
+) -> StdResult<DeeplinksResponse> {
+    if !include_subtypes {
+        return query_by_type(deps, type_, start_after, limit);
+    }
+    let type_ = resolve_ref(deps, type_)?;
+
+    // Instances of the subtypes are merged by id, each index is read only as
+    // far as the page goes
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let mut ranges = subtypes(deps.storage, type_)?
+        .into_iter()
+        .map(|subtype| deeplinks().idx.type_.prefix(subtype)
+            .range(deps.storage, start_after.map(|id| Bound::exclusive(id.u64())), None, Order::Ascending)
+            .filter(|item| !matches!(item, Ok((id, _)) if DELETED_IDS.has(deps.storage, *id))))
+        .collect::<Vec<_>>();
+    // Ids are unique across subtypes, so the heads are kept ordered by id
+    let mut heads = BTreeMap::new();
+    for (index, range) in ranges.iter_mut().enumerate() {
+        if let Some((id, deeplink)) = range.next().transpose()? {
+            heads.insert(id, (index, deeplink));
+        }
+    }
+    let mut instances = vec![];
+    while instances.len() < limit {
+        let (id, (index, deeplink)) = match heads.pop_first() {
+            Some(head) => head,
+            None => break,
+        };
+        instances.push(DeeplinkResponse { id: Uint64::new(id), deeplink });
+        if let Some((id, deeplink)) = ranges[index].next().transpose()? {
+            heads.insert(id, (index, deeplink));
+        }
+    }
+
+    Ok(DeeplinksResponse { deeplinks: instances })
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct DeeplinksResponse {
+    pub deeplinks: Vec<DeeplinkResponse>,
+}
+
 pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let cfg = CONFIG.load(deps.storage)?;
     Ok(ConfigResponse {
         admins: cfg.admins.into_iter().map(|a| a.into()).collect(),
-        executors: cfg.executors.into_iter().map(|a| a.into()).collect()
+        executors: cfg.executors.into_iter().map(|a| a.into()).collect(),
+        creation_policy: cfg.creation_policy,
+        max_object_size: cfg.max_object_size,
     })
 }
 
@@ -33,32 +462,155 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
 pub struct ConfigResponse {
     pub admins: Vec<String>,
     pub executors: Vec<String>,
+    pub creation_policy: CreationPolicy,
+    pub max_object_size: u32,
+}
+
+pub fn query_list_deeplinks(deps: Deps, start_after: Option<Uint64>, limit: Option<u32>) -> StdResult<DeeplinksResponse> {
+    paginate_deeplinks(deps, deeplinks().prefix(()), start_after, limit)
+}
+
+pub fn query_list_named(deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<NamedDeeplinksResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+
+    let named = NAMED_DEEPLINKS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(name, id)| NameResponse { name, id: Uint64::new(id) }))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(NamedDeeplinksResponse { named })
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct NamedDeeplinksResponse {
+    pub named: Vec<NameResponse>,
 }
 
-pub fn query_state(deps: Deps) -> StdResult<StateResponse> {
-    let deeplinks = DEEPLINKS
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .map(|i| i.unwrap())
-        .collect::<Vec<(u64, DeeplinkState)>>();
-    let named_deeplinks = NAMED_DEEPLINKS
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .map(|i| i.unwrap())
-        .collect::<Vec<(String, DeeplinkState)>>();
-    let deleted_deeplinks = DELETED_IDS
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .map(|i| i.unwrap().0)
-        .collect::<Vec<u64>>();
-
-    Ok(StateResponse {
-        deeplinks,
-        named_deeplinks,
-        deleted_deeplinks,
+pub fn query_list_deleted(deps: Deps, start_after: Option<Uint64>, limit: Option<u32>) -> StdResult<DeletedIdsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|id| Bound::exclusive(id.u64()));
+
+    let ids = DELETED_IDS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(Uint64::new))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(DeletedIdsResponse { ids })
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct DeletedIdsResponse {
+    pub ids: Vec<Uint64>,
+}
+
+/// Export of the store in id order, including deleted deeplinks.
+/// Pass `next` back as `start_after` until it is empty to sync a full snapshot.
+pub fn query_dump_state(deps: Deps, start_after: Option<Uint64>, limit: Option<u32>) -> StdResult<DumpStateResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|id| Bound::exclusive(id.u64()));
+
+    let entries = deeplinks()
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (id, deeplink) = item?;
+            Ok(DumpEntry {
+                id: Uint64::new(id),
+                deeplink,
+                names: names_of(deps.storage, id)?,
+                deleted: DELETED_IDS.has(deps.storage, id),
+                value: load_value(deps.storage, id)?,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let last_id = ID.load(deps.storage)?;
+    let next = match entries.last() {
+        Some(entry) if entries.len() == limit && entry.id.u64() < last_id => Some(entry.id),
+        _ => None,
+    };
+
+    Ok(DumpStateResponse {
+        entries,
+        last_id: Uint64::new(last_id),
+        next,
     })
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
-pub struct StateResponse {
-    pub deeplinks: Vec<(u64, DeeplinkState)>,
-    pub named_deeplinks: Vec<(String, DeeplinkState)>,
-    pub deleted_deeplinks: Vec<u64>,
-}
\ No newline at end of file
+pub struct DumpEntry {
+    pub id: Uint64,
+    pub deeplink: DeeplinkState,
+    pub names: Vec<String>,
+    pub deleted: bool,
+    pub value: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PackageResponse {
+    pub name: String,
+    pub id: Uint64,
+    pub publisher: String,
+    pub version: Option<String>,
+}
+
+/// Manifest in the format of InstallPackage
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ExportResponse {
+    pub package: Option<String>,
+    pub version: Option<String>,
+    pub dependencies: Vec<PackageDependency>,
+    pub deeplinks: Vec<NamedDeeplink>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PackageVersionResponse {
+    pub version: String,
+    /// Deeplink of type Package for the version
+    pub id: Uint64,
+    pub dependencies: Vec<PackageDependency>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PackageVersionsResponse {
+    pub package: String,
+    pub active: Option<String>,
+    pub versions: Vec<PackageVersionResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PackageIdsResponse {
+    pub package: String,
+    pub ids: Vec<Uint64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ValueResponse {
+    pub id: Uint64,
+    pub value: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ValueAtPathResponse {
+    pub id: Uint64,
+    pub path: String,
+    /// JSON text of the field, none if the path doesn't exist
+    pub value: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct DeeplinkWithValueResponse {
+    pub id: Uint64,
+    pub deeplink: DeeplinkState,
+    pub value: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct DumpStateResponse {
+    pub entries: Vec<DumpEntry>,
+    pub last_id: Uint64,
+    pub next: Option<Uint64>,
+}
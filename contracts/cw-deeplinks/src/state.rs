@@ -1,22 +1,222 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::Addr;
-use cw_storage_plus::{Item, Map};
+use std::collections::BTreeSet;
+use cosmwasm_std::{from_json, Addr, Order, StdResult, Storage, Timestamp};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+
+use crate::msg::{PackageDependency, TypeEnds};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct DeeplinkState {
     #[serde(rename = "type")]
-    pub type_: String,
-    pub from: String,
-    pub to: String,
+    pub type_: u64,
+    /// None if the deeplink has no such end
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    pub creator: Addr,
+    pub created_height: u64,
+    pub created_at: Timestamp,
+}
+
+pub struct DeeplinkIndexes<'a> {
+    pub type_: MultiIndex<'a, u64, DeeplinkState, u64>,
+    pub from: MultiIndex<'a, u64, DeeplinkState, u64>,
+    pub to: MultiIndex<'a, u64, DeeplinkState, u64>,
+    pub type_from: MultiIndex<'a, (u64, u64), DeeplinkState, u64>,
+    pub type_to: MultiIndex<'a, (u64, u64), DeeplinkState, u64>,
+    pub from_to: MultiIndex<'a, (u64, u64), DeeplinkState, u64>,
+    pub creator: MultiIndex<'a, Addr, DeeplinkState, u64>,
 }
 
+impl<'a> IndexList<DeeplinkState> for DeeplinkIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<DeeplinkState>> + '_> {
+        let v: Vec<&dyn Index<DeeplinkState>> = vec![&self.type_, &self.from, &self.to, &self.type_from, &self.type_to, &self.from_to, &self.creator];
+        Box::new(v.into_iter())
+    }
+}
+
+// Missing ends are indexed under 0, no deeplink has that id
+pub const NO_END: u64 = 0;
+
 pub const DEEPLINKS_KEY: &str = "deeplinks";
-pub const DEEPLINKS: Map<u64, DeeplinkState> = Map::new(DEEPLINKS_KEY);
+pub fn deeplinks<'a>() -> IndexedMap<'a, u64, DeeplinkState, DeeplinkIndexes<'a>> {
+    let indexes = DeeplinkIndexes {
+        type_: MultiIndex::new(|d: &DeeplinkState| d.type_, DEEPLINKS_KEY, "deeplinks__type"),
+        from: MultiIndex::new(|d: &DeeplinkState| d.from.unwrap_or(NO_END), DEEPLINKS_KEY, "deeplinks__from"),
+        to: MultiIndex::new(|d: &DeeplinkState| d.to.unwrap_or(NO_END), DEEPLINKS_KEY, "deeplinks__to"),
+        type_from: MultiIndex::new(|d: &DeeplinkState| (d.type_, d.from.unwrap_or(NO_END)), DEEPLINKS_KEY, "deeplinks__type_from"),
+        type_to: MultiIndex::new(|d: &DeeplinkState| (d.type_, d.to.unwrap_or(NO_END)), DEEPLINKS_KEY, "deeplinks__type_to"),
+        from_to: MultiIndex::new(|d: &DeeplinkState| (d.from.unwrap_or(NO_END), d.to.unwrap_or(NO_END)), DEEPLINKS_KEY, "deeplinks__from_to"),
+        creator: MultiIndex::new(|d: &DeeplinkState| d.creator.clone(), DEEPLINKS_KEY, "deeplinks__creator"),
+    };
+    IndexedMap::new(DEEPLINKS_KEY, indexes)
+}
 
 pub const NAMED_DEEPLINKS_KEY: &str = "named_deeplinks";
-pub const NAMED_DEEPLINKS: Map<&str, DeeplinkState> = Map::new(NAMED_DEEPLINKS_KEY);
+pub const NAMED_DEEPLINKS: Map<&str, u64> = Map::new(NAMED_DEEPLINKS_KEY);
+
+pub const DEEPLINK_NAMES_KEY: &str = "deeplink_names";
+pub const DEEPLINK_NAMES: Map<u64, String> = Map::new(DEEPLINK_NAMES_KEY);
+
+// Additional names resolving to a deeplink, the one in DEEPLINK_NAMES is canonical
+pub const DEEPLINK_ALIASES_KEY: &str = "deeplink_aliases";
+pub const DEEPLINK_ALIASES: Map<(u64, &str), bool> = Map::new(DEEPLINK_ALIASES_KEY);
+
+/// All names of a deeplink, the canonical one first
+pub fn names_of(storage: &dyn Storage, id: u64) -> StdResult<Vec<String>> {
+    let mut names = DEEPLINK_NAMES.may_load(storage, id)?.into_iter().collect::<Vec<_>>();
+    for alias in DEEPLINK_ALIASES.prefix(id).keys(storage, None, None, Order::Ascending) {
+        names.push(alias?);
+    }
+    Ok(names)
+}
+
+pub const TYPE_ENDS_KEY: &str = "type_ends";
+pub const TYPE_ENDS: Map<u64, TypeEnds> = Map::new(TYPE_ENDS_KEY);
+
+/// Registered package namespaces, names qualified as `package/name` belong to the publisher
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PackageState {
+    /// Deeplink of type PackageNamespace representing the package
+    pub id: u64,
+    pub publisher: Addr,
+    /// Active version, names of the package point to its deeplinks
+    #[serde(default)]
+    pub version: Option<String>,
+}
+
+pub const PACKAGES_KEY: &str = "packages";
+pub const PACKAGES: Map<&str, PackageState> = Map::new(PACKAGES_KEY);
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PackageVersionState {
+    /// Deeplink of type Package linked from the namespace with PackageVersion
+    pub id: u64,
+    pub dependencies: Vec<PackageDependency>,
+}
+
+pub const PACKAGE_VERSIONS_KEY: &str = "package_versions";
+pub const PACKAGE_VERSIONS: Map<(&str, &str), PackageVersionState> = Map::new(PACKAGE_VERSIONS_KEY);
+
+/// Deeplink installed by a package version, named while the version is active
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PackageEntry {
+    pub version: String,
+    pub name: String,
+}
+
+// Deeplinks installed by a package, by package and by id
+pub const PACKAGE_IDS_KEY: &str = "package_ids";
+pub const PACKAGE_IDS: Map<(&str, u64), PackageEntry> = Map::new(PACKAGE_IDS_KEY);
+
+pub const DEEPLINK_PACKAGES_KEY: &str = "deeplink_packages";
+pub const DEEPLINK_PACKAGES: Map<u64, String> = Map::new(DEEPLINK_PACKAGES_KEY);
+
+// Version requirements of active versions, by dependency and dependent package
+pub const PACKAGE_DEPENDENTS_KEY: &str = "package_dependents";
+pub const PACKAGE_DEPENDENTS: Map<(&str, &str), String> = Map::new(PACKAGE_DEPENDENTS_KEY);
+
+/// Splits a qualified `package/name` into its parts, global names have no package
+pub fn split_name(name: &str) -> Option<(&str, &str)> {
+    name.split_once('/')
+}
+
+/// Manifest entries with a local part starting with `_` are only referenced
+/// within the manifest and are not registered as names
+pub fn is_local_name(name: &str) -> bool {
+    split_name(name).map_or(name, |(_, local)| local).starts_with('_')
+}
+
+/// Looks up a name in the default package first and then globally,
+/// qualified names are looked up as is
+pub fn lookup_name(storage: &dyn Storage, name: &str, package: Option<&str>) -> StdResult<Option<u64>> {
+    if let (None, Some(package)) = (split_name(name), package) {
+        if let Some(id) = NAMED_DEEPLINKS.may_load(storage, &format!("{}/{}", package, name))? {
+            return Ok(Some(id));
+        }
+    }
+    NAMED_DEEPLINKS.may_load(storage, name)
+}
+
+/// Gives the parent types of a type, validation can add ones that aren't written yet
+pub type Parents<'a> = dyn Fn(u64) -> StdResult<Vec<u64>> + 'a;
+
+/// Parent types declared by live `Extends` links from the type
+pub fn type_parents(storage: &dyn Storage, type_: u64) -> StdResult<Vec<u64>> {
+    let extends = match NAMED_DEEPLINKS.may_load(storage, "Extends")? {
+        Some(extends) => extends,
+        None => return Ok(vec![]),
+    };
+    deeplinks().idx.type_from
+        .prefix((extends, type_))
+        .range(storage, None, None, Order::Ascending)
+        .filter(|item| !matches!(item, Ok((id, _)) if DELETED_IDS.has(storage, *id)))
+        .filter_map(|item| item.map(|(_, link)| link.to).transpose())
+        .collect()
+}
+
+/// Whether the type is the ancestor itself or extends it through any chain of parents
+pub fn is_subtype(type_: u64, ancestor: u64, parents: &Parents) -> StdResult<bool> {
+    let mut visited = BTreeSet::new();
+    let mut queue = vec![type_];
+    while let Some(next) = queue.pop() {
+        if next == ancestor {
+            return Ok(true);
+        }
+        if visited.insert(next) {
+            queue.extend(parents(next)?);
+        }
+    }
+    Ok(false)
+}
+
+/// The type together with every type extending it directly or indirectly
+pub fn subtypes(storage: &dyn Storage, type_: u64) -> StdResult<BTreeSet<u64>> {
+    let mut found = BTreeSet::from([type_]);
+    let extends = match NAMED_DEEPLINKS.may_load(storage, "Extends")? {
+        Some(extends) => extends,
+        None => return Ok(found),
+    };
+    let mut queue = vec![type_];
+    while let Some(next) = queue.pop() {
+        let children = deeplinks().idx.type_to
+            .prefix((extends, next))
+            .range(storage, None, None, Order::Ascending)
+            .filter(|item| !matches!(item, Ok((id, _)) if DELETED_IDS.has(storage, *id)))
+            .filter_map(|item| item.map(|(_, link)| link.from).transpose())
+            .collect::<StdResult<Vec<_>>>()?;
+        for child in children {
+            if found.insert(child) {
+                queue.push(child);
+            }
+        }
+    }
+    Ok(found)
+}
+
+// Names seeded by instantiate and core.json
+const CORE_JSON: &[u8] = include_bytes!("../core.json");
+
+#[derive(Deserialize)]
+struct CoreEntry {
+    id: String,
+}
+
+/// Names of core.json in manifest order
+pub fn core_names() -> StdResult<Vec<String>> {
+    let entries: Vec<CoreEntry> = from_json(CORE_JSON)?;
+    Ok(entries.into_iter().map(|entry| entry.id).collect())
+}
+
+// Names of core.json and of core deeplinks migrated in, recorded at instantiate and migrate
+pub const CORE_NAMES_KEY: &str = "core_names";
+pub const CORE_NAMES: Map<&str, bool> = Map::new(CORE_NAMES_KEY);
+
+/// Whether the name is a core one, core names only change through migrate
+pub fn is_core_name(storage: &dyn Storage, name: &str) -> bool {
+    CORE_NAMES.has(storage, name)
+}
 
 pub const ID_KEY: &str = "id";
 pub const ID: Item<u64> = Item::new("ID_KEY");
@@ -24,16 +224,52 @@ pub const ID: Item<u64> = Item::new("ID_KEY");
 pub const DELETED_IDS_KEY: &str = "deleted_ids";
 pub const DELETED_IDS: Map<u64, bool> = Map::new(DELETED_IDS_KEY);
 
+// Values are stored together with the height they were last written at
 pub const NUMBERS_VALUES_KEY: &str = "numbers_values";
 pub const NUMBERS_VALUES: Map<u64, (u64, u64)> = Map::new(NUMBERS_VALUES_KEY);
 
 pub const STRINGS_VALUES_KEY: &str = "strings_values";
 pub const STRINGS_VALUES: Map<u64, (u64, String)> = Map::new(STRINGS_VALUES_KEY);
 
+// Objects are kept as validated JSON text
+pub const OBJECTS_VALUES_KEY: &str = "objects_values";
+pub const OBJECTS_VALUES: Map<u64, (u64, String)> = Map::new(OBJECTS_VALUES_KEY);
+
+pub const DEFAULT_MAX_OBJECT_SIZE: u32 = 16 * 1024;
+
+fn default_max_object_size() -> u32 {
+    DEFAULT_MAX_OBJECT_SIZE
+}
+
+/// Who can create deeplinks through CreateDeeplink and CreateDeeplinks,
+/// admins are always allowed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CreationPolicy {
+    #[default]
+    Open,
+    AdminsOnly,
+    AllowList { creators: Vec<Addr> },
+    /// Listed types can be created only by their creators, other types are open
+    PerType { types: Vec<TypeAllowList> },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TypeAllowList {
+    #[serde(rename = "type")]
+    pub type_: u64,
+    pub creators: Vec<Addr>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     pub admins: Vec<Addr>,
-    pub executors : Vec<Addr>
+    pub executors : Vec<Addr>,
+    #[serde(default)]
+    pub creation_policy: CreationPolicy,
+    /// Size limit of object values in bytes of JSON text
+    #[serde(default = "default_max_object_size")]
+    pub max_object_size: u32,
 }
 
 impl Config {
@@ -51,6 +287,25 @@ impl Config {
         self.is_admin(addr)
     }
 
+    pub fn can_modify_deeplink(&self, addr: &str, deeplink: &DeeplinkState) -> bool {
+        self.is_admin(addr) || deeplink.creator.as_ref() == addr
+    }
+
+    pub fn can_create(&self, addr: &str, type_: u64) -> bool {
+        if self.is_admin(addr) {
+            return true;
+        }
+        match &self.creation_policy {
+            CreationPolicy::Open => true,
+            CreationPolicy::AdminsOnly => false,
+            CreationPolicy::AllowList { creators } => creators.iter().any(|a| a.as_ref() == addr),
+            CreationPolicy::PerType { types } => match types.iter().find(|t| t.type_ == type_) {
+                Some(t) => t.creators.iter().any(|a| a.as_ref() == addr),
+                None => true,
+            },
+        }
+    }
+
     pub fn can_execute(&self, addr: &str) -> bool {
         self.is_executor(addr)
     }
@@ -1,14 +1,104 @@
 #[cfg(test)]
-mod tests {
+mod contract_tests {
     use std::fs::File;
     use std::io::BufReader;
-    use cosmwasm_std::{Addr, from_binary, StdError, Uint64};
+    use cosmwasm_std::{Addr, Deps, DepsMut, from_json, Response, StdError, Storage, Uint64};
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
     use serde_json::to_string_pretty;
-    use crate::contract::{execute, instantiate, query};
+    use cw_storage_plus::Map;
+    use crate::contract::{execute, instantiate, migrate, query};
+    use crate::ContractError;
     use crate::msg::*;
-    use crate::query::StateResponse;
-    use crate::state::{Config, DeeplinkState};
+    use crate::query::{ConfigResponse, DeeplinksResponse, DeeplinkWithValueResponse, ValueAtPathResponse, ValueResponse, DeletedIdsResponse, DumpStateResponse, ExportResponse, IsInstanceOfResponse, NameResponse, NamedDeeplinksResponse, NamesResponse, PackageIdsResponse, PackageResponse, PackageVersionsResponse, Diagnostic, ValidateDeeplinksResponse};
+    use crate::state::{Config, CONFIG, CreationPolicy, ID, TypeAllowList, DEEPLINK_NAMES, deeplinks, DeeplinkState, DELETED_IDS, NAMED_DEEPLINKS, is_core_name};
+
+    fn core_deeplinks() -> Vec<NamedDeeplink> {
+        let file = File::open("core.json").expect("file should open read only");
+        let reader = BufReader::new(file);
+        serde_json::from_reader(reader).unwrap()
+    }
+
+    // Core deeplinks get sequential ids, Type and Any are seeded by instantiate
+    fn core_id(name: &str) -> u64 {
+        core_deeplinks().iter().position(|deeplink| deeplink.id == name).unwrap() as u64 + 1
+    }
+
+    fn entry_error(index: u32, error: ContractError) -> ContractError {
+        ContractError::BatchEntry { index, error: Box::new(error) }
+    }
+
+    // Storage as left by 0.0.1 after seeding core.json: deeplinks reference each other by
+    // name, names hold copies of the state and instantiate named Any with a state of its own
+    fn seed_legacy_core(storage: &mut dyn Storage) -> u64 {
+        let legacy_deeplinks: Map<u64, serde_json::Value> = Map::new("deeplinks");
+        let legacy_named: Map<&str, serde_json::Value> = Map::new("named_deeplinks");
+        let state = |type_: &str, from: &str, to: &str| serde_json::json!({ "type": type_, "from": from, "to": to });
+        CONFIG.save(storage, &Config {
+            admins: vec![Addr::unchecked("admin1")],
+            executors: vec![],
+            creation_policy: CreationPolicy::Open,
+            max_object_size: 1024,
+        }).unwrap();
+
+        legacy_deeplinks.save(storage, 1, &state("Type", "Any", "Any")).unwrap();
+        legacy_named.save(storage, "Type", &state("Type", "Any", "Any")).unwrap();
+        legacy_deeplinks.save(storage, 2, &state("Any", "Null", "Null")).unwrap();
+        legacy_named.save(storage, "Any", &state("Type", "Null", "Null")).unwrap();
+        let mut id = 2;
+        for entry in core_deeplinks() {
+            id += 1;
+            let entry_state = state(&entry.type_, entry.from.as_deref().unwrap_or("Any"), entry.to.as_deref().unwrap_or("Any"));
+            legacy_deeplinks.save(storage, id, &entry_state).unwrap();
+            legacy_named.save(storage, entry.id.as_str(), &entry_state).unwrap();
+        }
+        id += 1;
+        legacy_deeplinks.save(storage, id, &state("Contain", "Port", "Space")).unwrap();
+        ID.save(storage, &id).unwrap();
+        cw2::set_contract_version(storage, "cw-deeplinks", "0.0.1").unwrap();
+        id
+    }
+
+    fn instantiate_with_core(mut deps: DepsMut) {
+        let msg = InstantiateMsg {
+            admins: vec!["admin1".to_string(), "admin2".to_string()],
+            executers: vec!["exec1".to_string(), "exec2".to_string()],
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.branch(), mock_env(), info, msg).unwrap();
+
+        // Type and Any are created by instantiate
+        for deeplink in core_deeplinks().into_iter().skip(2) {
+            let msg = ExecuteMsg::CreatedNamedDeeplink {
+                name: deeplink.id.clone(),
+                deeplink: deeplink.into(),
+                package: None,
+                upsert: false,
+            };
+            let info = mock_info("admin1", &[]);
+            execute(deps.branch(), mock_env(), info, msg).unwrap();
+        }
+    }
+
+    /// Widget and Part types with Part link `ab` between Widgets `a` and `b`,
+    /// created by admin1 after the core, returns the id of Widget
+    fn seed_widgets(mut deps: DepsMut) -> Uint64 {
+        let named = |name: &str, type_: &str, from: Option<&str>, to: Option<&str>| ExecuteMsg::CreatedNamedDeeplink {
+            name: name.to_string(),
+            deeplink: Deeplink { type_: type_.into(), from: from.map(Into::into), to: to.map(Into::into) },
+            package: None,
+            upsert: false,
+        };
+        for msg in [
+            named("Widget", "Type", Some("Any"), Some("Any")),
+            named("Part", "Type", Some("Widget"), Some("Widget")),
+            named("a", "Widget", None, None),
+            named("b", "Widget", None, None),
+            named("ab", "Part", Some("a"), Some("b")),
+        ] {
+            execute(deps.branch(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+        }
+        Uint64::new(core_deeplinks().len() as u64 + 1)
+    }
 
     #[test]
     fn test_instantiate() {
@@ -21,7 +111,7 @@ mod tests {
         let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
         assert_eq!(0, res.messages.len());
 
-        let config: Config = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        let config: Config = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
         assert_eq!(config.admins, vec![Addr::unchecked("admin1"), Addr::unchecked("admin2")]);
         assert_eq!(config.executors, vec![Addr::unchecked("exec1"), Addr::unchecked("exec2")]);
     }
@@ -41,15 +131,13 @@ mod tests {
         let deeplinks: Vec<NamedDeeplink> = serde_json::from_reader(reader).unwrap();
 
         let mut errors = vec![];
-        for deeplink in deeplinks {
+        for deeplink in deeplinks.into_iter().skip(2) {
             let link = deeplink.clone();
             let msg = ExecuteMsg::CreatedNamedDeeplink {
-                name: link.id,
-                deeplink: Deeplink {
-                    type_: link.type_,
-                    from: link.from,
-                    to: link.to,
-                }
+                name: link.id.clone(),
+                deeplink: link.into(),
+                package: None,
+                upsert: false,
             };
             let info = mock_info("admin1", &[]);
             let res = execute(deps.as_mut(), mock_env(), info, msg);
@@ -63,8 +151,19 @@ mod tests {
 
         assert_eq!(errors.len(), 0);
 
-        let debug_state: StateResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::DebugState { }).unwrap()).unwrap();
-        println!("{}", to_string_pretty(&debug_state).unwrap());
+        let mut entries = vec![];
+        let mut start_after = None;
+        loop {
+            let dump: DumpStateResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::DumpState { start_after, limit: Some(1000) }).unwrap()).unwrap();
+            entries.extend(dump.entries);
+            start_after = dump.next;
+            if start_after.is_none() {
+                break;
+            }
+        }
+        println!("{}", to_string_pretty(&entries).unwrap());
+        assert_eq!(entries.len(), 175);
+        assert!(entries.iter().all(|entry| entry.names.len() == 1 && !entry.deleted));
     }
 
     #[test]
@@ -82,15 +181,13 @@ mod tests {
         let deeplinks: Vec<NamedDeeplink> = serde_json::from_reader(reader).unwrap();
 
         let mut errors = vec![];
-        for deeplink in deeplinks {
+        for deeplink in deeplinks.into_iter().skip(2) {
             let link = deeplink.clone();
             let msg = ExecuteMsg::CreatedNamedDeeplink {
-                name: link.id,
-                deeplink: Deeplink {
-                    type_: link.type_,
-                    from: link.from,
-                    to: link.to,
-                }
+                name: link.id.clone(),
+                deeplink: link.into(),
+                package: None,
+                upsert: false,
             };
             let info = mock_info("admin1", &[]);
             let res = execute(deps.as_mut(), mock_env(), info, msg);
@@ -99,43 +196,1828 @@ mod tests {
         assert_eq!(errors.len(), 0);
 
         let deeplink = Deeplink {
-            type_: "Type".to_string(),
-            from: Some("Query".to_string()),
-            to: Some("String".to_string()),
+            type_: "Type".into(),
+            from: Some("Query".into()),
+            to: Some("String".into()),
         };
-        let msg = ExecuteMsg::CreateDeeplink { deeplink };
+        let msg = ExecuteMsg::CreateDeeplink { deeplink, package: None };
         let info = mock_info("admin2", &[]);
         let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
         println!("{:?}", res);
         assert_eq!(res.attributes[0].value, "create_deeplink");
 
-        let last_id: Uint64 = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
-        let deeplink_state1: DeeplinkState = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Deeplink { id: last_id }).unwrap()).unwrap();
-        assert_eq!(deeplink_state1.type_, "Type");
-        assert_eq!(deeplink_state1.from, "Query");
-        assert_eq!(deeplink_state1.to, "String");
+        let last_id: Uint64 = from_json(query(deps.as_ref(), mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
+        let deeplink_state1: DeeplinkState = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Deeplink { id: last_id }).unwrap()).unwrap();
+        assert_eq!(deeplink_state1.type_, core_id("Type"));
+        assert_eq!(deeplink_state1.from, Some(core_id("Query")));
+        assert_eq!(deeplink_state1.to, Some(core_id("String")));
+    }
+
+    #[test]
+    fn test_create_deeplink_by_id() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+
+        // Anonymous deeplinks can be used as endpoints by id
+        let msg = ExecuteMsg::CreateDeeplinks {
+            deeplinks: vec![
+                Deeplink { type_: "Selector".into(), from: None, to: None },
+                Deeplink { type_: "Query".into(), from: None, to: None },
+            ],
+            package: None,
+            continue_on_error: false,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+        let query_id: Uint64 = from_json(query(deps.as_ref(), mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
+        let selector_id = query_id - Uint64::one();
+
+        let msg = ExecuteMsg::CreateDeeplink {
+            deeplink: Deeplink {
+                type_: DeeplinkRef::Id(Uint64::new(core_id("SelectorFilter"))),
+                from: Some(DeeplinkRef::Id(selector_id)),
+                to: Some(DeeplinkRef::Id(query_id)),
+            },
+            package: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+        let last_id: Uint64 = from_json(query(deps.as_ref(), mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
+        let state: DeeplinkState = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Deeplink { id: last_id }).unwrap()).unwrap();
+        assert_eq!(state, DeeplinkState {
+            type_: core_id("SelectorFilter"),
+            from: Some(selector_id.u64()),
+            to: Some(query_id.u64()),
+            creator: Addr::unchecked("admin1"),
+            created_height: mock_env().block.height,
+            created_at: mock_env().block.time,
+        });
+
+        let msg = ExecuteMsg::CreateDeeplink {
+            deeplink: Deeplink {
+                type_: "Contain".into(),
+                from: Some(DeeplinkRef::Id(Uint64::new(1000))),
+                to: Some(DeeplinkRef::Id(query_id)),
+            },
+            package: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::FromNotExists { from: "1000".to_string() });
+    }
+
+    #[test]
+    fn test_created_ids() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+        let created = |id: u64, name: Option<&str>| CreatedDeeplink { id: Uint64::new(id), name: name.map(|n| n.to_string()) };
+        let attrs = |res: &Response<_>| res.attributes.iter().map(|a| (a.key.clone(), a.value.clone())).collect::<Vec<_>>();
+        let pair = |key: &str, value: String| (key.to_string(), value);
+        let next = core_deeplinks().len() as u64 + 1;
+
+        let msg = ExecuteMsg::CreateDeeplink {
+            deeplink: Deeplink { type_: "Type".into(), from: Some("Query".into()), to: Some("String".into()) },
+            package: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        let data: CreateDeeplinksResponse = from_json(res.data.clone().unwrap()).unwrap();
+        assert_eq!(data.deeplinks, vec![created(next, None)]);
+        assert_eq!(attrs(&res), vec![
+            pair("action", "create_deeplink".to_string()),
+            pair("id", next.to_string()),
+            pair("type", core_id("Type").to_string()),
+            pair("from", core_id("Query").to_string()),
+            pair("to", core_id("String").to_string()),
+        ]);
+
+        // One group of attributes per deeplink, missing ends as `_`
+        let msg = ExecuteMsg::CreateDeeplinks {
+            deeplinks: vec![
+                Deeplink { type_: "Port".into(), from: None, to: None },
+                Deeplink { type_: "Contain".into(), from: Some(next.into()), to: None },
+            ],
+            package: None,
+            continue_on_error: false,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        let data: CreateDeeplinksResponse = from_json(res.data.clone().unwrap()).unwrap();
+        assert_eq!(data.deeplinks, vec![created(next + 1, None), created(next + 2, None)]);
+        assert_eq!(attrs(&res)[5..], [
+            pair("id", (next + 2).to_string()),
+            pair("type", core_id("Contain").to_string()),
+            pair("from", next.to_string()),
+            pair("to", "_".to_string()),
+        ]);
+
+        let msg = ExecuteMsg::CreatedNamedDeeplink {
+            name: "Listener".to_string(),
+            deeplink: Deeplink { type_: "Type".into(), from: None, to: None },
+            package: None,
+            upsert: false,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+        let data: CreateDeeplinksResponse = from_json(res.data.clone().unwrap()).unwrap();
+        assert_eq!(data.deeplinks, vec![created(next + 3, Some("Listener"))]);
+        assert_eq!(attrs(&res)[1], pair("id", (next + 3).to_string()));
+    }
+
+    #[test]
+    fn test_create_deeplinks_errors() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+        let last_id = |deps: Deps| from_json::<Uint64>(query(deps, mock_env(), QueryMsg::LastId {}).unwrap()).unwrap().u64();
+        let msg = |continue_on_error: bool| ExecuteMsg::CreateDeeplinks {
+            deeplinks: vec![
+                Deeplink { type_: "Port".into(), from: None, to: None },
+                Deeplink { type_: "Missing".into(), from: None, to: None },
+                Deeplink { type_: "Contain".into(), from: Some("Port".into()), to: Some("Nowhere".into()) },
+                Deeplink { type_: "Space".into(), from: None, to: None },
+            ],
+            package: None,
+            continue_on_error,
+        };
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg(false)).unwrap_err();
+        assert_eq!(err, entry_error(1, ContractError::TypeNotExists { type_: "Missing".to_string() }));
+        assert_eq!(err.to_string(), "Entry 1: Type not exists: Missing");
+
+        // The mock storage keeps writes of failed messages, the chain reverts them
+        let next = last_id(deps.as_ref()) + 1;
+        let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg(true)).unwrap();
+        let data: CreateDeeplinksResponse = from_json(res.data.unwrap()).unwrap();
+        let ids = data.deeplinks.iter().map(|created| created.id.u64()).collect::<Vec<_>>();
+        assert_eq!(ids, vec![next, next + 1]);
+        assert_eq!(data.failures, vec![
+            EntryFailure { index: 1, error: "Type not exists: Missing".to_string() },
+            EntryFailure { index: 2, error: "To not exists: Nowhere".to_string() },
+        ]);
+        assert_eq!(last_id(deps.as_ref()), next + 1);
+    }
+
+    #[test]
+    fn test_resolve_names() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+
+        let res: NameResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ResolveName { name: "Contain".to_string() }).unwrap()).unwrap();
+        assert_eq!(res.id, Uint64::new(core_id("Contain")));
+
+        let res: NamesResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Names { id: Uint64::new(core_id("Any")) }).unwrap()).unwrap();
+        assert_eq!(res.names, vec!["Any".to_string()]);
+
+        // Instantiate and core.json agree on the core types
+        let any: DeeplinkState = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Deeplink { id: Uint64::new(core_id("Any")) }).unwrap()).unwrap();
+        assert_eq!(any.type_, core_id("Type"));
+
+        assert!(query(deps.as_ref(), mock_env(), QueryMsg::ResolveName { name: "NotExists".to_string() }).is_err());
+    }
+
+    #[test]
+    fn test_migrate_legacy_deeplinks() {
+        let mut deps = mock_dependencies();
+        let last_id = seed_legacy_core(&mut deps.storage);
+        migrate(deps.as_mut(), mock_env(), MigrateMsg::default()).unwrap();
+        assert!(is_core_name(&deps.storage, "Contain"));
+        assert!(!is_core_name(&deps.storage, "Widget"));
+
+        // Core entries were created after Type and Any of instantiate
+        let legacy_id = |name: &str| core_id(name) + 2;
+        let state = |id: u64| deeplinks().load(&deps.storage, id).unwrap();
+        let link = state(last_id);
+        assert_eq!(link.type_, legacy_id("Contain"));
+        assert_eq!((link.from, link.to), (Some(legacy_id("Port")), Some(legacy_id("Space"))));
+        assert_eq!(link.creator, mock_env().contract.address);
+        let value = state(legacy_id("spaceValue"));
+        assert_eq!(value.type_, legacy_id("Value"));
+        assert_eq!((value.from, value.to), (Some(legacy_id("Space")), Some(legacy_id("String"))));
+
+        // Types keep Any as their constraint, Null of the instantiated Any is dropped
+        let any = Some(legacy_id("Any"));
+        assert_eq!((state(legacy_id("Contain")).from, state(legacy_id("Contain")).to), (any, any));
+        assert_eq!(state(2).type_, legacy_id("Any"));
+        assert_eq!((state(2).from, state(2).to), (None, None));
+        let res: DeeplinksResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ByFrom {
+            from: legacy_id("Port").into(),
+            start_after: None,
+            limit: None,
+        }).unwrap()).unwrap();
+        assert!(res.deeplinks.iter().any(|d| d.id.u64() == last_id));
+    }
+
+    #[test]
+    fn test_migrate_legacy_layouts() {
+        let mut deps = mock_dependencies();
+        let legacy_deeplinks: Map<u64, serde_json::Value> = Map::new("deeplinks");
+
+        // 0.0.3 ids, with one value still in the 0.0.1 layout
+        for (name, id) in [("Type", 1), ("Any", 2), ("Contain", 3)] {
+            NAMED_DEEPLINKS.save(&mut deps.storage, name, &id).unwrap();
+            DEEPLINK_NAMES.save(&mut deps.storage, id, &name.to_string()).unwrap();
+        }
+        legacy_deeplinks.save(&mut deps.storage, 1, &serde_json::json!({ "type": 1, "from": 2, "to": 2 })).unwrap();
+        legacy_deeplinks.save(&mut deps.storage, 2, &serde_json::json!({ "type": 1, "from": null, "to": null })).unwrap();
+        legacy_deeplinks.save(&mut deps.storage, 3, &serde_json::json!({ "type": 1, "from": 2, "to": 2 })).unwrap();
+        legacy_deeplinks.save(&mut deps.storage, 4, &serde_json::json!({ "type": "Contain", "from": "Type", "to": "Null" })).unwrap();
+        ID.save(&mut deps.storage, &4).unwrap();
+        cw2::set_contract_version(&mut deps.storage, "cw-deeplinks", "0.0.3").unwrap();
+        migrate(deps.as_mut(), mock_env(), MigrateMsg::default()).unwrap();
+
+        let state = deeplinks().load(&deps.storage, 4).unwrap();
+        assert_eq!((state.type_, state.from, state.to), (3, Some(1), None));
+        assert_eq!(state.creator, mock_env().contract.address);
+        let res: DeeplinksResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ByType {
+            type_: "Contain".into(),
+            start_after: None,
+            limit: None,
+        }).unwrap()).unwrap();
+        assert_eq!(res.deeplinks.iter().map(|d| d.id.u64()).collect::<Vec<_>>(), vec![4]);
+    }
+
+    #[test]
+    fn test_migrate_named_deeplinks() {
+        let mut deps = mock_dependencies();
+        seed_legacy_core(&mut deps.storage);
+        migrate(deps.as_mut(), mock_env(), MigrateMsg::default()).unwrap();
+
+        // Type and Any were created again from core.json, the names point to the newer copies
+        let legacy_id = |name: &str| core_id(name) + 2;
+        for name in ["Type", "Any", "Package", "Contain", "Query", "spaceValue"] {
+            assert_eq!(NAMED_DEEPLINKS.load(&deps.storage, name).unwrap(), legacy_id(name));
+            assert_eq!(DEEPLINK_NAMES.load(&deps.storage, legacy_id(name)).unwrap(), name);
+        }
+        assert!(!DEEPLINK_NAMES.has(&deps.storage, 1));
+        assert!(!DEEPLINK_NAMES.has(&deps.storage, 2));
+        let named = NAMED_DEEPLINKS.keys(&deps.storage, None, None, cosmwasm_std::Order::Ascending).count();
+        assert_eq!(named, core_deeplinks().len());
+
+        // Without core.json, names created after instantiate follow Type and Any
+        // has no copy of its deeplink
+        let mut deps = mock_dependencies();
+        let legacy_deeplinks: Map<u64, serde_json::Value> = Map::new("deeplinks");
+        let legacy_named: Map<&str, serde_json::Value> = Map::new("named_deeplinks");
+        let state = |type_: &str, from: &str, to: &str| serde_json::json!({ "type": type_, "from": from, "to": to });
+        legacy_deeplinks.save(&mut deps.storage, 1, &state("Type", "Any", "Any")).unwrap();
+        legacy_named.save(&mut deps.storage, "Type", &state("Type", "Any", "Any")).unwrap();
+        legacy_deeplinks.save(&mut deps.storage, 2, &state("Any", "Null", "Null")).unwrap();
+        legacy_named.save(&mut deps.storage, "Any", &state("Type", "Null", "Null")).unwrap();
+        legacy_deeplinks.save(&mut deps.storage, 3, &state("Type", "Any", "Any")).unwrap();
+        legacy_named.save(&mut deps.storage, "Widget", &state("Type", "Any", "Any")).unwrap();
+        legacy_deeplinks.save(&mut deps.storage, 4, &state("Widget", "Type", "Any")).unwrap();
+        ID.save(&mut deps.storage, &4).unwrap();
+        cw2::set_contract_version(&mut deps.storage, "cw-deeplinks", "0.0.1").unwrap();
+        migrate(deps.as_mut(), mock_env(), MigrateMsg::default()).unwrap();
+
+        for (name, id) in [("Type", 1), ("Any", 2), ("Widget", 3)] {
+            assert_eq!(NAMED_DEEPLINKS.load(&deps.storage, name).unwrap(), id);
+            assert_eq!(DEEPLINK_NAMES.load(&deps.storage, id).unwrap(), name);
+        }
+        let state = deeplinks().load(&deps.storage, 4).unwrap();
+        assert_eq!((state.type_, state.from, state.to), (3, Some(1), Some(2)));
+    }
+
+    #[test]
+    fn test_query_by_indexes() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+
+        let ids = |res: DeeplinksResponse| res.deeplinks.into_iter().map(|d| d.id.u64()).collect::<Vec<u64>>();
+
+        // Outgoing links of containTree, paginated
+        let res: DeeplinksResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ByFrom {
+            from: "containTree".into(),
+            start_after: None,
+            limit: Some(4),
+        }).unwrap()).unwrap();
+        let first_page = ids(res);
+        assert_eq!(first_page, vec![
+            core_id("containTreeContain"),
+            core_id("containTreeAny"),
+            core_id("containTreeSymbol"),
+            core_id("containTreeThen"),
+        ]);
+        let res: DeeplinksResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ByFrom {
+            from: DeeplinkRef::Id(Uint64::new(core_id("containTree"))),
+            start_after: Some(Uint64::new(first_page[3])),
+            limit: Some(4),
+        }).unwrap()).unwrap();
+        assert_eq!(ids(res), vec![core_id("containTreeRejected"), core_id("containTreeResolved")]);
+
+        // Links between Rule and Selector, whatever their type
+        let res: DeeplinksResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ByFromTo {
+            from: "Rule".into(),
+            to: "Selector".into(),
+            start_after: Some(Uint64::new(core_id("RuleSubject"))),
+            limit: None,
+        }).unwrap()).unwrap();
+        assert_eq!(ids(res), vec![core_id("RuleObject"), core_id("RuleAction")]);
+
+        // Incoming links of Handler
+        let res: DeeplinksResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ByTo {
+            to: "Handler".into(),
+            start_after: None,
+            limit: None,
+        }).unwrap()).unwrap();
+        assert_eq!(ids(res), vec![
+            core_id("HandleInsert"),
+            core_id("HandleUpdate"),
+            core_id("HandleDelete"),
+            core_id("handlersTreeHandler"),
+            core_id("HandleSchedule"),
+            core_id("HandleRoute"),
+            core_id("routeTreeHandler"),
+            core_id("HandleClient"),
+        ]);
+
+        let res: DeeplinksResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ByType {
+            type_: "ExecutionProvider".into(),
+            start_after: None,
+            limit: None,
+        }).unwrap()).unwrap();
+        assert_eq!(ids(res), vec![core_id("JSExecutionProvider"), core_id("JSminiExecutionProvider")]);
+
+        let res: DeeplinksResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ByTypeFrom {
+            type_: "TreeIncludeNode".into(),
+            from: "routeTree".into(),
+            start_after: None,
+            limit: None,
+        }).unwrap()).unwrap();
+        assert_eq!(res.deeplinks.len(), 4);
+
+        let res: DeeplinksResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ByTypeTo {
+            type_: "Value".into(),
+            to: "Object".into(),
+            start_after: None,
+            limit: None,
+        }).unwrap()).unwrap();
+        assert_eq!(ids(res), vec![
+            core_id("promiseResultValueRelationTable"),
+            core_id("focusValue"),
+            core_id("queryValue"),
+            core_id("fixedValue"),
+            core_id("handlingErrorValue"),
+        ]);
+
+        // Deleted deeplinks are skipped
+        let msg = ExecuteMsg::CreateDeeplink {
+            deeplink: Deeplink { type_: "Value".into(), from: Some("Query".into()), to: Some("Object".into()) },
+            package: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+        let id: Uint64 = from_json(query(deps.as_ref(), mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
+        let msg = ExecuteMsg::DeleteDeeplink { id, mode: None };
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+        let res: DeeplinksResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ByTypeTo {
+            type_: "Value".into(),
+            to: "Object".into(),
+            start_after: None,
+            limit: None,
+        }).unwrap()).unwrap();
+        assert_eq!(res.deeplinks.len(), 5);
+    }
+
+    #[test]
+    fn test_list_state() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+
+        for (name, deeplink) in [
+            ("Gadget", Deeplink { type_: "Type".into(), from: Some("Any".into()), to: Some("Any".into()) }),
+            ("gadgetValue", Deeplink { type_: "Value".into(), from: Some("Gadget".into()), to: Some("Object".into()) }),
+        ] {
+            let msg = ExecuteMsg::CreatedNamedDeeplink { name: name.to_string(), deeplink, package: None, upsert: false };
+            execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+        }
+        let gadget = Uint64::new(176);
+        let msg = ExecuteMsg::DeleteDeeplink { id: gadget, mode: Some(DeleteMode::Cascade) };
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+
+        // Page size is capped
+        let res: DeeplinksResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ListDeeplinks { start_after: None, limit: Some(1000) }).unwrap()).unwrap();
+        assert_eq!(res.deeplinks.len(), 100);
+        let res: DeeplinksResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ListDeeplinks { start_after: Some(res.deeplinks[99].id), limit: Some(1000) }).unwrap()).unwrap();
+        assert_eq!(res.deeplinks.len(), 75);
+
+        let res: NamedDeeplinksResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ListNamed { start_after: Some("Active".to_string()), limit: Some(2) }).unwrap()).unwrap();
+        assert_eq!(res.named, vec![
+            NameResponse { name: "AllowAdmin".to_string(), id: Uint64::new(core_id("AllowAdmin")) },
+            NameResponse { name: "AllowDelete".to_string(), id: Uint64::new(core_id("AllowDelete")) },
+        ]);
+
+        let res: DeletedIdsResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ListDeleted { start_after: None, limit: None }).unwrap()).unwrap();
+        assert_eq!(res.ids, vec![gadget, gadget + Uint64::one()]);
+
+        let res: DumpStateResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::DumpState { start_after: Some(gadget - Uint64::one()), limit: Some(1) }).unwrap()).unwrap();
+        assert!(res.entries[0].deleted);
+        assert_eq!(res.entries[0].names, vec!["Gadget".to_string()]);
+        assert_eq!(res.next, Some(gadget));
+        assert_eq!(res.last_id, Uint64::new(177));
     }
 
     #[test]
     fn test_update_deeplink() {
         let mut deps = mock_dependencies();
-        let msg = InstantiateMsg {
-            admins: vec!["admin1".to_string(), "admin2".to_string()],
-            executers: vec!["exec1".to_string(), "exec2".to_string()],
+        instantiate_with_core(deps.as_mut());
+
+        let deeplink = Deeplink {
+            type_: "Type".into(),
+            from: Some("Query".into()),
+            to: Some("String".into()),
         };
-        let info = mock_info("creator", &[]);
-        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreateDeeplink { deeplink, package: None };
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+        let id: Uint64 = from_json(query(deps.as_ref(), mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
+
+        // Only admins can update
+        let deeplink = Deeplink {
+            type_: "Contain".into(),
+            from: Some("Query".into()),
+            to: Some("Number".into()),
+        };
+        let msg = ExecuteMsg::UpdateDeeplink { id, deeplink: deeplink.clone(), package: None };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("user", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let msg = ExecuteMsg::UpdateDeeplink { id, deeplink, package: None };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+        assert_eq!(res.attributes[0].value, "update_deeplink");
+
+        let state: DeeplinkState = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Deeplink { id }).unwrap()).unwrap();
+        assert_eq!(state.type_, core_id("Contain"));
+        assert_eq!(state.from, Some(core_id("Query")));
+        assert_eq!(state.to, Some(core_id("Number")));
+
+        // The new value is re-validated
+        let deeplink = Deeplink {
+            type_: "Contain".into(),
+            from: Some("Query".into()),
+            to: Some("NotExists".into()),
+        };
+        let msg = ExecuteMsg::UpdateDeeplink { id, deeplink, package: None };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::ToNotExists { to: "NotExists".to_string() });
+
+        // Unknown and deleted ids are rejected
+        let deeplink = Deeplink {
+            type_: "Contain".into(),
+            from: Some("Query".into()),
+            to: Some("String".into()),
+        };
+        let msg = ExecuteMsg::UpdateDeeplink { id: Uint64::new(1000), deeplink: deeplink.clone(), package: None };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::DeeplinkNotExists { id: Uint64::new(1000) });
+
+        let msg = ExecuteMsg::DeleteDeeplink { id, mode: None };
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+        let msg = ExecuteMsg::UpdateDeeplink { id, deeplink, package: None };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::DeletedDeeplink { id });
+    }
+
+    #[test]
+    fn test_update_named_deeplink() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+
+        let msg = ExecuteMsg::CreatedNamedDeeplink {
+            name: "Note".to_string(),
+            deeplink: Deeplink {
+                type_: "Type".into(),
+                from: None,
+                to: None,
+            },
+            package: None,
+            upsert: false,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+        let id: Uint64 = from_json(query(deps.as_ref(), mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
+
+        let msg = ExecuteMsg::UpdateDeeplink {
+            id,
+            deeplink: Deeplink {
+                type_: "Type".into(),
+                from: Some("User".into()),
+                to: Some("String".into()),
+            },
+            package: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+
+        // Links typed by the named deeplink are checked against its new ends
+        let msg = ExecuteMsg::CreateDeeplink {
+            deeplink: Deeplink {
+                type_: "Note".into(),
+                from: Some("Query".into()),
+                to: Some("String".into()),
+            },
+            package: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::TypeConflict { .. }));
+    }
+
+    #[test]
+    fn test_protected_names() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+
+        let named = |name: &str, type_: &str, upsert: bool| ExecuteMsg::CreatedNamedDeeplink {
+            name: name.to_string(),
+            deeplink: Deeplink { type_: type_.into(), from: None, to: None },
+            package: None,
+            upsert,
+        };
+        let resolve = |deps: Deps, name: &str| from_json::<NameResponse>(query(deps, mock_env(), QueryMsg::ResolveName { name: name.to_string() }).unwrap()).unwrap().id;
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), named("Any", "Type", false)).unwrap_err();
+        assert_eq!(err, ContractError::AlreadyExists { name: "Any".to_string() });
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), named("Any", "Type", true)).unwrap_err();
+        assert_eq!(err, ContractError::CoreName { name: "Any".to_string() });
+        let msg = ExecuteMsg::UpdateDeeplink {
+            id: Uint64::new(core_id("Contain")),
+            deeplink: Deeplink { type_: "Type".into(), from: None, to: None },
+            package: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::CoreName { name: "Contain".to_string() });
+
+        // Core deeplinks are never deleted, so their names can't be freed
+        let msg = ExecuteMsg::DeleteDeeplink { id: Uint64::new(core_id("Router")), mode: Some(DeleteMode::Cascade) };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::CoreName { name: "Router".to_string() });
+        DELETED_IDS.save(&mut deps.storage, core_id("Router"), &true).unwrap();
+        let msg = ExecuteMsg::PurgeDeeplinks { limit: None };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::CoreName { name: "Router".to_string() });
+        DELETED_IDS.remove(&mut deps.storage, core_id("Router"));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), named("Router", "Type", true)).unwrap_err();
+        assert_eq!(err, ContractError::CoreName { name: "Router".to_string() });
+
+        // Other names are replaced in place only with upsert
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), named("Note", "Type", false)).unwrap();
+        let note = resolve(deps.as_ref(), "Note");
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), named("Note", "Contain", false)).unwrap_err();
+        assert_eq!(err, ContractError::AlreadyExists { name: "Note".to_string() });
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), named("Note", "Query", true)).unwrap();
+        assert_eq!(resolve(deps.as_ref(), "Note"), note);
+        let state: DeeplinkState = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Deeplink { id: note }).unwrap()).unwrap();
+        assert_eq!(state.type_, core_id("Query"));
+
+        // Migration changes core deeplinks in place and adds new ones
+        let msg = MigrateMsg {
+            core_deeplinks: vec![
+                NamedDeeplink { id: "Contain".to_string(), type_: "Type".to_string(), from: Some("Any".to_string()), to: Some("Query".to_string()), value: None },
+                NamedDeeplink { id: "Version".to_string(), type_: "Type".to_string(), from: None, to: None, value: None },
+            ],
+        };
+        migrate(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(resolve(deps.as_ref(), "Contain"), Uint64::new(core_id("Contain")));
+        let state: DeeplinkState = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Deeplink { id: Uint64::new(core_id("Contain")) }).unwrap()).unwrap();
+        assert_eq!(state.to, Some(core_id("Query")));
+        let version: DeeplinkState = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Deeplink { id: resolve(deps.as_ref(), "Version") }).unwrap()).unwrap();
+        assert_eq!(version.creator, mock_env().contract.address);
+    }
+
+    #[test]
+    fn test_batch() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+
+        let create = |type_: &str, from: Option<&str>, to: Option<&str>, placeholder: Option<&str>| BatchOp::Create {
+            deeplink: Deeplink { type_: type_.into(), from: from.map(Into::into), to: to.map(Into::into) },
+            placeholder: placeholder.map(|p| p.to_string()),
+        };
+        let last_id = |deps: Deps| from_json::<Uint64>(query(deps, mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
+        let first = last_id(deps.as_ref()) + Uint64::one();
+
+        let ops = vec![
+            create("Space", None, None, Some("home")),
+            create("Port", None, None, None),
+            create("Contain", Some("$home"), Some("$1"), Some("link")),
+            BatchOp::SetValue { id: "$home".into(), value: Value::String("home".to_string()) },
+            BatchOp::SetValue { id: "$1".into(), value: Value::Number(Uint64::new(8080)) },
+            BatchOp::Update {
+                id: "$link".into(),
+                deeplink: Deeplink { type_: "Contain".into(), from: Some("$0".into()), to: None },
+            },
+            create("Port", None, None, Some("tmp")),
+            BatchOp::Delete { id: "$tmp".into(), mode: None },
+        ];
+        let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), ExecuteMsg::Batch { ops, package: None }).unwrap();
+        let data: BatchResponse = from_json(res.data.unwrap()).unwrap();
+        let id = |offset: u64| first + Uint64::new(offset);
+        assert_eq!(data.placeholders, [
+            ("$0", id(0)), ("$home", id(0)), ("$1", id(1)),
+            ("$2", id(2)), ("$link", id(2)), ("$6", id(3)), ("$tmp", id(3)),
+        ].iter().map(|(placeholder, id)| (placeholder.to_string(), *id)).collect());
+
+        let state: DeeplinkState = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Deeplink { id: id(2) }).unwrap()).unwrap();
+        assert_eq!((state.from, state.to), (Some(id(0).u64()), None));
+        let res: ValueResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Value { id: id(1) }).unwrap()).unwrap();
+        assert_eq!(res.value, Some(Value::Number(Uint64::new(8080))));
+        let res: DeletedIdsResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ListDeleted { start_after: None, limit: None }).unwrap()).unwrap();
+        assert_eq!(res.ids, vec![id(3)]);
+
+        // Placeholders are local to the batch and checked in order
+        let ops = vec![create("Contain", Some("$1"), None, None), create("Port", None, None, None)];
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), ExecuteMsg::Batch { ops, package: None }).unwrap_err();
+        assert_eq!(err, entry_error(0, ContractError::UnknownPlaceholder { placeholder: "$1".to_string() }));
+        let ops = vec![create("Port", None, None, Some("3"))];
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), ExecuteMsg::Batch { ops, package: None }).unwrap_err();
+        assert_eq!(err, entry_error(0, ContractError::InvalidPlaceholder { placeholder: "3".to_string() }));
+        let ops = vec![BatchOp::SetValue { id: id(0).u64().into(), value: Value::String("office".to_string()) }];
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), ExecuteMsg::Batch { ops, package: None }).unwrap_err();
+        assert_eq!(err, entry_error(0, ContractError::ValueAlreadySet { id: id(0) }));
+
+        // Operations keep the permissions of their standalone messages
+        let ops = vec![create("Port", None, None, None), BatchOp::Delete { id: id(2).u64().into(), mode: None }];
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), ExecuteMsg::Batch { ops, package: None }).unwrap_err();
+        assert_eq!(err, entry_error(1, ContractError::Unauthorized {}));
+
+        let msg = ExecuteMsg::CreatedNamedDeeplink {
+            name: "$home".to_string(),
+            deeplink: Deeplink { type_: "Space".into(), from: None, to: None },
+            package: None,
+            upsert: false,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidName { name: "$home".to_string() });
+    }
+
+    #[test]
+    fn test_rename_and_aliases() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+
+        let entry = |id: &str, type_: &str, from: Option<&str>| NamedDeeplink {
+            id: id.to_string(),
+            type_: type_.to_string(),
+            from: from.map(|f| f.to_string()),
+            to: None,
+            value: None,
+        };
+        let msg = ExecuteMsg::InstallPackage {
+            package: "graph".to_string(),
+            version: "1.0.0".to_string(),
+            deeplinks: vec![entry("Node", "Type", None), entry("Tag", "Type", Some("Node")), entry("a", "Node", None)],
+            dependencies: vec![],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        let resolve = |deps: Deps, name: &str| query(deps, mock_env(), QueryMsg::ResolveName { name: name.to_string() })
+            .ok()
+            .map(|res| from_json::<NameResponse>(res).unwrap().id);
+        let names = |deps: Deps, id: Uint64| from_json::<NamesResponse>(query(deps, mock_env(), QueryMsg::Names { id }).unwrap()).unwrap();
+        let node = resolve(deps.as_ref(), "graph/Node").unwrap();
+        let rename = |name: &str, new_name: &str| ExecuteMsg::RenameDeeplink { name: name.to_string(), new_name: new_name.to_string() };
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), rename("graph/Node", "graph/Vertex")).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), rename("graph/Node", "graph/a")).unwrap_err();
+        assert_eq!(err, ContractError::AlreadyExists { name: "graph/a".to_string() });
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), rename("Contain", "Include")).unwrap_err();
+        assert_eq!(err, ContractError::CoreName { name: "Contain".to_string() });
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), rename("graph/Missing", "graph/Other")).unwrap_err();
+        assert_eq!(err, ContractError::NameNotExists { name: "graph/Missing".to_string() });
+
+        // The id stays, so links typed by it keep working under the new name
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), rename("graph/Node", "graph/Vertex")).unwrap();
+        assert_eq!(resolve(deps.as_ref(), "graph/Node"), None);
+        assert_eq!(resolve(deps.as_ref(), "graph/Vertex"), Some(node));
+        let msg = ExecuteMsg::CreateDeeplink {
+            deeplink: Deeplink { type_: "graph/Tag".into(), from: Some("graph/a".into()), to: None },
+            package: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        let export: ExportResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ExportPackage { package: "graph".to_string(), limit: None }).unwrap()).unwrap();
+        assert!(export.deeplinks.contains(&entry("Vertex", "Type", None)));
+
+        // Aliases resolve to the same id, the first name stays canonical
+        let alias = |id: Uint64, alias: &str| ExecuteMsg::AddAlias { id, alias: alias.to_string() };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), alias(node, "graph/Node")).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), alias(node, "Node")).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), alias(node, "Node")).unwrap();
+        assert_eq!(resolve(deps.as_ref(), "Node"), Some(node));
+        let res = names(deps.as_ref(), node);
+        assert_eq!(res.canonical, Some("graph/Vertex".to_string()));
+        assert_eq!(res.names, vec!["graph/Vertex", "Node", "graph/Node"]);
+
+        let msg = ExecuteMsg::SetCanonicalName { name: "graph/Node".to_string() };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        let res = names(deps.as_ref(), node);
+        assert_eq!(res.canonical, Some("graph/Node".to_string()));
+        assert_eq!(res.names, vec!["graph/Node", "Node", "graph/Vertex"]);
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), ExecuteMsg::RemoveAlias { alias: "graph/Node".to_string() }).unwrap_err();
+        assert_eq!(err, ContractError::NotAlias { name: "graph/Node".to_string() });
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), ExecuteMsg::RemoveAlias { alias: "graph/Vertex".to_string() }).unwrap();
+        assert_eq!(resolve(deps.as_ref(), "graph/Vertex"), None);
+
+        // Renamed aliases stay aliases, an unnamed deeplink gets a canonical name
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), rename("Node", "Vertex")).unwrap();
+        assert_eq!(names(deps.as_ref(), node).names, vec!["graph/Node", "Vertex"]);
+        let tagged: Uint64 = from_json(query(deps.as_ref(), mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), alias(tagged, "tagged")).unwrap();
+        assert_eq!(names(deps.as_ref(), tagged).canonical, Some("tagged".to_string()));
+
+        // Uninstall frees the aliases too
+        let msg = ExecuteMsg::UninstallPackage { package: "graph".to_string(), mode: Some(DeleteMode::Cascade) };
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+        assert_eq!(resolve(deps.as_ref(), "Vertex"), None);
+        assert_eq!(resolve(deps.as_ref(), "tagged"), Some(tagged));
     }
 
     #[test]
     fn test_delete_deeplink() {
         let mut deps = mock_dependencies();
-        let msg = InstantiateMsg {
-            admins: vec!["admin1".to_string(), "admin2".to_string()],
-            executers: vec!["exec1".to_string(), "exec2".to_string()],
+        instantiate_with_core(deps.as_mut());
+        let widget = seed_widgets(deps.as_mut());
+
+        // Restrict refuses to leave dangling references, referrers are only shown to who may delete
+        let msg = ExecuteMsg::DeleteDeeplink { id: widget, mode: None };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("user", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::DeeplinkHasReferrers {
+            id: widget,
+            referrers: (1..4).map(|offset| widget + Uint64::new(offset)).collect(),
+        });
+
+        // Cascade removes dependents transitively
+        let msg = ExecuteMsg::DeleteDeeplink { id: widget, mode: Some(DeleteMode::Cascade) };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+        let affected = (0..5).map(|offset| (widget.u64() + offset).to_string()).collect::<Vec<String>>().join(",");
+        assert_eq!(res.attributes[2].value, affected);
+
+        let res: DeletedIdsResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ListDeleted { start_after: None, limit: None }).unwrap()).unwrap();
+        assert_eq!(res.ids.len(), 5);
+
+        // Deleted deeplinks can't be referenced or deleted again
+        let msg = ExecuteMsg::CreateDeeplink {
+            deeplink: Deeplink { type_: "Widget".into(), from: None, to: None },
+            package: None,
         };
-        let info = mock_info("creator", &[]);
-        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::DeletedDeeplink { id: widget });
+
+        let msg = ExecuteMsg::DeleteDeeplink { id: widget, mode: None };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::DeletedDeeplink { id: widget });
+
+        let msg = ExecuteMsg::DeleteDeeplink { id: Uint64::new(1000), mode: None };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::DeeplinkNotExists { id: Uint64::new(1000) });
+    }
+
+    #[test]
+    fn test_deeplink_ownership() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+
+        let mut env = mock_env();
+        env.block.height += 10;
+        let msg = ExecuteMsg::CreateDeeplinks {
+            deeplinks: vec![
+                Deeplink { type_: "Query".into(), from: None, to: None },
+                Deeplink { type_: "Query".into(), from: None, to: None },
+            ],
+            package: None,
+            continue_on_error: false,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg).unwrap();
+        let id: Uint64 = from_json(query(deps.as_ref(), mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
+
+        let state: DeeplinkState = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Deeplink { id }).unwrap()).unwrap();
+        assert_eq!(state.creator, Addr::unchecked("alice"));
+        assert_eq!(state.created_height, env.block.height);
+        assert_eq!(state.created_at, env.block.time);
+
+        let res: DeeplinksResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ByCreator {
+            creator: "alice".to_string(),
+            start_after: None,
+            limit: None,
+        }).unwrap()).unwrap();
+        assert_eq!(res.deeplinks.iter().map(|d| d.id).collect::<Vec<_>>(), vec![id - Uint64::one(), id]);
+
+        // Only the creator or an admin can modify
+        let deeplink = Deeplink { type_: "Selector".into(), from: None, to: None };
+        let msg = ExecuteMsg::UpdateDeeplink { id, deeplink: deeplink.clone(), package: None };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg.clone()).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("admin2", &[]), msg).unwrap();
+
+        // Update keeps the creation metadata
+        let state: DeeplinkState = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Deeplink { id }).unwrap()).unwrap();
+        assert_eq!(state.creator, Addr::unchecked("alice"));
+        assert_eq!(state.type_, core_id("Selector"));
+
+        let msg = ExecuteMsg::DeleteDeeplink { id, mode: None };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+
+        // Cascade can't reach links of other creators
+        let msg = ExecuteMsg::CreateDeeplink {
+            deeplink: Deeplink { type_: "Contain".into(), from: Some(DeeplinkRef::Id(id - Uint64::one())), to: Some("Query".into()) },
+            package: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap();
+        let msg = ExecuteMsg::DeleteDeeplink { id: id - Uint64::one(), mode: Some(DeleteMode::Cascade) };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn test_restore_deeplink() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+
+        let id = seed_widgets(deps.as_mut());
+        let (part, a, ab) = (id + Uint64::one(), id + Uint64::new(2), id + Uint64::new(4));
+        let msg = ExecuteMsg::DeleteDeeplink { id, mode: Some(DeleteMode::Cascade) };
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+
+        // Endpoints must be restored first
+        let msg = ExecuteMsg::RestoreDeeplink { id: ab };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::DeletedDeeplink { id: part });
+
+        let msg = ExecuteMsg::RestoreDeeplink { id };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("user", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let res = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg.clone()).unwrap();
+        assert_eq!(res.attributes[0].value, "restore_deeplink");
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::DeeplinkNotDeleted { id });
+
+        for id in [part, a, a + Uint64::one(), ab] {
+            let msg = ExecuteMsg::RestoreDeeplink { id };
+            execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+        }
+        let state: DeeplinkState = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Deeplink { id: ab }).unwrap()).unwrap();
+        assert_eq!(state.from, Some(a.u64()));
+    }
+
+    #[test]
+    fn test_purge_deeplinks() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+
+        let id = seed_widgets(deps.as_mut());
+        let msg = ExecuteMsg::DeleteDeeplink { id, mode: Some(DeleteMode::Cascade) };
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::PurgeDeeplinks { limit: Some(3) };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("user", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let res = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg.clone()).unwrap();
+        assert_eq!(res.attributes[1].value.split(',').count(), 3);
+
+        let res: DeletedIdsResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ListDeleted { start_after: None, limit: None }).unwrap()).unwrap();
+        assert_eq!(res.ids.len(), 2);
+        let res = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+        assert_eq!(res.attributes[1].value.split(',').count(), 2);
+
+        // Purged deeplinks are gone from the store, indexes and names
+        assert!(query(deps.as_ref(), mock_env(), QueryMsg::Deeplink { id }).is_err());
+        assert!(query(deps.as_ref(), mock_env(), QueryMsg::ResolveName { name: "Widget".to_string() }).is_err());
+        let res: DeeplinksResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ByType {
+            type_: DeeplinkRef::Id(id),
+            start_after: None,
+            limit: None,
+        }).unwrap()).unwrap();
+        assert!(res.deeplinks.is_empty());
+
+        let msg = ExecuteMsg::RestoreDeeplink { id };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::DeeplinkNotExists { id });
+    }
+
+    #[test]
+    fn test_creation_policy() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+
+        let create = |type_: &str| ExecuteMsg::CreateDeeplink {
+            deeplink: Deeplink { type_: type_.into(), from: None, to: None },
+            package: None,
+        };
+        let update_policy = |policy: CreationPolicyMsg| ExecuteMsg::UpdateCreationPolicy { policy };
+
+        // Open by default
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), create("Query")).unwrap();
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), update_policy(CreationPolicyMsg::AdminsOnly)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), update_policy(CreationPolicyMsg::AdminsOnly)).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), create("Query")).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info("admin2", &[]), create("Query")).unwrap();
+
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), update_policy(CreationPolicyMsg::AllowList {
+            creators: vec!["alice".to_string()],
+        })).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), create("Query")).unwrap();
+        let msg = ExecuteMsg::CreateDeeplinks { deeplinks: vec![Deeplink { type_: "Query".into(), from: None, to: None }], package: None, continue_on_error: false };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap_err();
+        assert_eq!(err, entry_error(0, ContractError::Unauthorized {}));
+
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), update_policy(CreationPolicyMsg::PerType {
+            types: vec![TypeAllowListMsg { type_: "Selector".into(), creators: vec!["bob".to_string()] }],
+        })).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), create("Query")).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), create("Selector")).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), create("Selector")).unwrap();
+
+        // Package entries are created under the same policy
+        let install = |package: &str| ExecuteMsg::InstallPackage {
+            package: package.to_string(),
+            version: "0.1.0".to_string(),
+            deeplinks: vec![NamedDeeplink { id: "s".to_string(), type_: "Selector".to_string(), from: None, to: None, value: None }],
+            dependencies: vec![],
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), install("alice")).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), install("bob")).unwrap();
+
+        // Changing the type of an owned deeplink is covered by the policy too
+        let id: Uint64 = from_json(query(deps.as_ref(), mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
+        let msg = ExecuteMsg::UpdateDeeplink { id: id - Uint64::one(), deeplink: Deeplink { type_: "Selector".into(), from: None, to: None }, package: None };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), update_policy(CreationPolicyMsg::PerType {
+            types: vec![TypeAllowListMsg { type_: "Missing".into(), creators: vec![] }],
+        })).unwrap_err();
+        assert_eq!(err, ContractError::TypeNotExists { type_: "Missing".to_string() });
+
+        let res: ConfigResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(res.creation_policy, CreationPolicy::PerType {
+            types: vec![TypeAllowList { type_: core_id("Selector"), creators: vec![Addr::unchecked("bob")] }],
+        });
+    }
+
+    #[test]
+    fn test_half_links() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+
+        let msg = ExecuteMsg::CreatedNamedDeeplink {
+            name: "Tag".to_string(),
+            deeplink: Deeplink { type_: "Type".into(), from: None, to: None },
+            package: None,
+            upsert: false,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+        let tag = |from: Option<&str>, to: Option<&str>| ExecuteMsg::CreateDeeplink {
+            deeplink: Deeplink { type_: "Tag".into(), from: from.map(Into::into), to: to.map(Into::into) },
+            package: None,
+        };
+        let last_state = |deps: Deps| {
+            let id: Uint64 = from_json(query(deps, mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
+            from_json::<DeeplinkState>(query(deps, mock_env(), QueryMsg::Deeplink { id }).unwrap()).unwrap()
+        };
+
+        // Missing ends are stored as none, not as Any
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), tag(Some("Query"), None)).unwrap();
+        let state = last_state(deps.as_ref());
+        assert_eq!((state.from, state.to), (Some(core_id("Query")), None));
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), tag(None, Some("Query"))).unwrap();
+        assert_eq!(last_state(deps.as_ref()).from, None);
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), tag(None, None)).unwrap();
+        let res: DeeplinksResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ByTo {
+            to: "Any".into(),
+            start_after: None,
+            limit: None,
+        }).unwrap()).unwrap();
+        assert!(res.deeplinks.iter().all(|deeplink| deeplink.deeplink.creator != "alice"));
+
+        let ends = TypeEnds { from: EndRule::Required, to: EndRule::Forbidden };
+        let msg = ExecuteMsg::SetTypeEnds { type_: "Tag".into(), ends };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+        let res: TypeEnds = from_json(query(deps.as_ref(), mock_env(), QueryMsg::TypeEnds { type_: "Tag".into() }).unwrap()).unwrap();
+        assert_eq!(res, ends);
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), tag(None, None)).unwrap_err();
+        assert_eq!(err, ContractError::EndRequired { type_: "Tag".to_string(), end: "from".to_string() });
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), tag(Some("Query"), Some("Query"))).unwrap_err();
+        assert_eq!(err, ContractError::EndForbidden { type_: "Tag".to_string(), end: "to".to_string() });
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), tag(Some("Query"), None)).unwrap();
+
+        // Only the present end is checked against the type
+        let msg = ExecuteMsg::CreateDeeplink {
+            deeplink: Deeplink { type_: "Then".into(), from: None, to: Some("Query".into()) },
+            package: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::TypeConflict { .. }));
+
+        // Migration drops substituted Any ends of deeplinks, types keep them
+        let any = core_id("Any");
+        let node = last_state(deps.as_ref());
+        deeplinks().save(&mut deps.storage, 1000, &DeeplinkState { from: Some(any), to: Some(any), ..node }).unwrap();
+        cw2::set_contract_version(&mut deps.storage, "cw-deeplinks", "0.0.4").unwrap();
+        migrate(deps.as_mut(), mock_env(), MigrateMsg::default()).unwrap();
+        let state = deeplinks().load(&deps.storage, 1000).unwrap();
+        assert_eq!((state.from, state.to), (None, None));
+        let state = deeplinks().load(&deps.storage, core_id("Contain")).unwrap();
+        assert_eq!((state.from, state.to), (Some(any), Some(any)));
+    }
+
+    #[test]
+    fn test_type_hierarchy() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+
+        let link = |type_: &str, from: &str, to: &str| Deeplink { type_: type_.into(), from: Some(from.into()), to: Some(to.into()) };
+        let named = |name: &str, deeplink: Deeplink| ExecuteMsg::CreatedNamedDeeplink { name: name.to_string(), deeplink, package: None, upsert: false };
+        let node = || Deeplink { type_: "Type".into(), from: None, to: None };
+        for msg in [
+            named("Node", node()),
+            named("Person", node()),
+            named("Relation", link("Type", "Node", "Node")),
+            named("alice", Deeplink { type_: "Person".into(), from: None, to: None }),
+            named("bob", Deeplink { type_: "Person".into(), from: None, to: None }),
+            named("root", Deeplink { type_: "Node".into(), from: None, to: None }),
+        ] {
+            execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+        }
+        let id = |deps: Deps, name: &str| from_json::<NameResponse>(query(deps, mock_env(), QueryMsg::ResolveName { name: name.to_string() }).unwrap()).unwrap().id;
+        let create = |deeplink: Deeplink| ExecuteMsg::CreateDeeplink { deeplink, package: None };
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), create(link("Relation", "alice", "bob"))).unwrap_err();
+        assert!(matches!(err, ContractError::TypeConflict { .. }));
+
+        // Persons are nodes once the type extends it, which only its owners declare
+        let err = execute(deps.as_mut(), mock_env(), mock_info("carol", &[]), create(link("Extends", "Person", "Node"))).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), create(link("Extends", "Person", "Node"))).unwrap();
+        let person_node: Uint64 = from_json(query(deps.as_ref(), mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), create(link("Relation", "alice", "bob"))).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), create(link("Relation", "root", "alice"))).unwrap();
+        let relation: Uint64 = from_json(query(deps.as_ref(), mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), create(link("Extends", "Node", "Person"))).unwrap_err();
+        assert_eq!(err, ContractError::TypeCycle { type_: "Node".to_string() });
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), create(link("Extends", "Node", "Node"))).unwrap_err();
+        assert_eq!(err, ContractError::TypeCycle { type_: "Node".to_string() });
+
+        let is_instance = |deps: Deps, id: Uint64, type_: &str| from_json::<IsInstanceOfResponse>(query(deps, mock_env(), QueryMsg::IsInstanceOf {
+            id,
+            type_: type_.into(),
+        }).unwrap()).unwrap().instance;
+        assert!(is_instance(deps.as_ref(), id(deps.as_ref(), "alice"), "Node"));
+        assert!(is_instance(deps.as_ref(), id(deps.as_ref(), "alice"), "Person"));
+        assert!(!is_instance(deps.as_ref(), id(deps.as_ref(), "root"), "Person"));
+        assert!(!is_instance(deps.as_ref(), relation, "Node"));
+
+        // Subtypes hold the value kind declared on their parents
+        let value_link = link("Value", "Node", "String");
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), create(value_link)).unwrap();
+        let msg = ExecuteMsg::SetValue { id: id(deps.as_ref(), "alice"), value: Value::String("Alice".to_string()) };
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+
+        let instances = |deps: Deps, include_subtypes: bool, start_after: Option<Uint64>| from_json::<DeeplinksResponse>(query(deps, mock_env(), QueryMsg::InstancesOf {
+            type_: "Node".into(),
+            include_subtypes,
+            start_after,
+            limit: Some(2),
+        }).unwrap()).unwrap().deeplinks.into_iter().map(|deeplink| deeplink.id).collect::<Vec<_>>();
+        assert_eq!(instances(deps.as_ref(), false, None), vec![id(deps.as_ref(), "root")]);
+        let page = instances(deps.as_ref(), true, None);
+        assert_eq!(page, vec![id(deps.as_ref(), "alice"), id(deps.as_ref(), "bob")]);
+        assert_eq!(instances(deps.as_ref(), true, page.last().copied()), vec![id(deps.as_ref(), "root")]);
+
+        // Subtypes declared in a manifest apply to the rest of it
+        let entry = |id: &str, type_: &str, from: Option<&str>, to: Option<&str>| NamedDeeplink {
+            id: id.to_string(),
+            type_: type_.to_string(),
+            from: from.map(|f| f.to_string()),
+            to: to.map(|t| t.to_string()),
+            value: None,
+        };
+        let msg = ExecuteMsg::InstallPackage {
+            package: "family".to_string(),
+            version: "1.0.0".to_string(),
+            deeplinks: vec![
+                entry("parent", "Relation", Some("kid"), Some("alice")),
+                entry("kid", "Kid", None, None),
+                entry("Kid", "Type", None, None),
+                entry("kidNode", "Extends", Some("Kid"), Some("Person")),
+            ],
+            dependencies: vec![],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("carol", &[]), msg).unwrap();
+        assert!(is_instance(deps.as_ref(), id(deps.as_ref(), "family/kid"), "Node"));
+
+        // Reversing an Extends link replaces its old parent instead of closing a cycle
+        let msg = ExecuteMsg::UpdateDeeplink { id: person_node, deeplink: link("Extends", "Node", "Person"), package: None };
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+        assert!(is_instance(deps.as_ref(), id(deps.as_ref(), "root"), "Person"));
+        assert!(!is_instance(deps.as_ref(), id(deps.as_ref(), "alice"), "Node"));
+    }
+
+    #[test]
+    fn test_validate_deeplinks() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+
+        let msg = ExecuteMsg::CreateDeeplink { deeplink: Deeplink { type_: "Space".into(), from: None, to: None }, package: None };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        let space: Uint64 = from_json(query(deps.as_ref(), mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), ExecuteMsg::DeleteDeeplink { id: space, mode: None }).unwrap();
+        let msg = ExecuteMsg::SetTypeEnds {
+            type_: "Contain".into(),
+            ends: TypeEnds { from: EndRule::Required, to: EndRule::Optional },
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+
+        let link = |type_: DeeplinkRef, from: Option<DeeplinkRef>, to: Option<DeeplinkRef>| Deeplink { type_, from, to };
+        let deeplinks = vec![
+            link("Port".into(), None, None),
+            link("Missing".into(), Some("Nowhere".into()), None),
+            link("Resolved".into(), Some("Port".into()), Some("Nowhere".into())),
+            link("Contain".into(), None, Some(space.u64().into())),
+            link("Extends".into(), Some("Port".into()), Some("Port".into())),
+        ];
+        let msg = QueryMsg::ValidateDeeplinks { deeplinks: deeplinks.clone(), package: None, sender: None };
+        let res: ValidateDeeplinksResponse = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let end = |end: &str| end.to_string();
+        let diagnostics = res.results.iter().map(|result| (result.valid, result.diagnostics.clone())).collect::<Vec<_>>();
+        assert_eq!(diagnostics, vec![
+            (true, vec![]),
+            (false, vec![
+                Diagnostic::NotExists { end: end("type"), reference: "Missing".to_string() },
+                Diagnostic::NotExists { end: end("from"), reference: "Nowhere".to_string() },
+            ]),
+            (false, vec![
+                Diagnostic::NotExists { end: end("to"), reference: "Nowhere".to_string() },
+                Diagnostic::TypeMismatch {
+                    end: end("from"),
+                    expected: Uint64::new(core_id("Promise")),
+                    received: Uint64::new(core_id("Type")),
+                },
+            ]),
+            (false, vec![
+                Diagnostic::Deleted { end: end("to"), id: space },
+                Diagnostic::EndRequired { end: end("from") },
+            ]),
+            (false, vec![Diagnostic::TypeCycle {}]),
+        ]);
+        assert_eq!(res.results[4].index, 4);
+
+        // Matches what CreateDeeplinks accepts
+        let msg = ExecuteMsg::CreateDeeplinks { deeplinks, package: None, continue_on_error: true };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+        let data: CreateDeeplinksResponse = from_json(res.data.unwrap()).unwrap();
+        assert_eq!(data.deeplinks.len(), 1);
+        assert_eq!(data.failures.iter().map(|failure| failure.index).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+
+        // Authorization is reported for the given sender
+        let msg = ExecuteMsg::UpdateCreationPolicy { policy: CreationPolicyMsg::PerType {
+            types: vec![TypeAllowListMsg { type_: "Port".into(), creators: vec!["bob".to_string()] }],
+        } };
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+        let validate = |deps: Deps, sender: &str| {
+            let msg = QueryMsg::ValidateDeeplinks { deeplinks: vec![link("Port".into(), None, None)], package: None, sender: Some(sender.to_string()) };
+            from_json::<ValidateDeeplinksResponse>(query(deps, mock_env(), msg).unwrap()).unwrap().results[0].diagnostics.clone()
+        };
+        assert_eq!(validate(deps.as_ref(), "alice"), vec![Diagnostic::Unauthorized {}]);
+        assert_eq!(validate(deps.as_ref(), "bob"), vec![]);
+    }
+
+    #[test]
+    fn test_values() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+
+        let msg = ExecuteMsg::CreateDeeplinks {
+            deeplinks: vec![
+                Deeplink { type_: "Port".into(), from: None, to: None },
+                Deeplink { type_: "Space".into(), from: None, to: None },
+            ],
+            package: None,
+            continue_on_error: false,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        let space: Uint64 = from_json(query(deps.as_ref(), mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
+        let port = space - Uint64::one();
+
+        let res: ValueResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Value { id: port }).unwrap()).unwrap();
+        assert_eq!(res.value, None);
+
+        let msg = ExecuteMsg::SetValue { id: port, value: Value::Number(Uint64::new(8080)) };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg.clone()).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::ValueAlreadySet { id: port });
+
+        let msg = ExecuteMsg::UpdateValue { id: space, value: Value::String("home".to_string()) };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::ValueNotSet { id: space });
+        let msg = ExecuteMsg::SetValue { id: space, value: Value::String("home".to_string()) };
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::UpdateValue { id: port, value: Value::Number(Uint64::new(443)) };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+
+        let res: DeeplinkWithValueResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::DeeplinkWithValue { id: port }).unwrap()).unwrap();
+        assert_eq!(res.deeplink.type_, core_id("Port"));
+        assert_eq!(res.value, Some(Value::Number(Uint64::new(443))));
+        let res: ValueResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Value { id: space }).unwrap()).unwrap();
+        assert_eq!(res.value, Some(Value::String("home".to_string())));
+
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), ExecuteMsg::ClearValue { id: space }).unwrap();
+        let res: ValueResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Value { id: space }).unwrap()).unwrap();
+        assert_eq!(res.value, None);
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), ExecuteMsg::ClearValue { id: space }).unwrap_err();
+        assert_eq!(err, ContractError::ValueNotSet { id: space });
+
+        // Values of deleted deeplinks are kept for restore but can't be changed
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), ExecuteMsg::DeleteDeeplink { id: port, mode: None }).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), ExecuteMsg::ClearValue { id: port }).unwrap_err();
+        assert_eq!(err, ContractError::DeletedDeeplink { id: port });
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), ExecuteMsg::RestoreDeeplink { id: port }).unwrap();
+        let res: ValueResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Value { id: port }).unwrap()).unwrap();
+        assert_eq!(res.value, Some(Value::Number(Uint64::new(443))));
+    }
+
+    #[test]
+    fn test_value_kinds() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+
+        let msg = ExecuteMsg::CreateDeeplinks {
+            deeplinks: vec![
+                Deeplink { type_: "Port".into(), from: None, to: None },
+                Deeplink { type_: "Selector".into(), from: None, to: None },
+            ],
+            package: None,
+            continue_on_error: false,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        let selector: Uint64 = from_json(query(deps.as_ref(), mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
+        let port = selector - Uint64::one();
+
+        // portValue declares Number
+        let msg = ExecuteMsg::SetValue { id: port, value: Value::String("8080".to_string()) };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::ValueKindMismatch {
+            id: port,
+            type_: "Port".to_string(),
+            expected: "Number".to_string(),
+            received: "String".to_string(),
+        });
+        let msg = ExecuteMsg::SetValue { id: port, value: Value::Number(Uint64::new(8080)) };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        let msg = ExecuteMsg::UpdateValue { id: port, value: Value::String("8080".to_string()) };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::ValueKindMismatch { .. }));
+
+        let msg = ExecuteMsg::SetValue { id: selector, value: Value::String("*".to_string()) };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::ValueNotDeclared { id: selector, type_: "Selector".to_string() });
+
+        // Only those allowed to modify a type declare its value
+        let declare = |from: DeeplinkRef| ExecuteMsg::CreateDeeplink {
+            deeplink: Deeplink { type_: "Value".into(), from: Some(from), to: Some("String".into()) },
+            package: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), declare("Selector".into())).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let msg_tag = ExecuteMsg::CreateDeeplink {
+            deeplink: Deeplink { type_: "Type".into(), from: None, to: None },
+            package: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg_tag).unwrap();
+        let tag: Uint64 = from_json(query(deps.as_ref(), mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), declare(DeeplinkRef::Id(tag))).unwrap();
+
+        // Declaring a value on the type allows it
+        let msg_declare = ExecuteMsg::CreatedNamedDeeplink {
+            name: "selectorValue".to_string(),
+            deeplink: Deeplink { type_: "Value".into(), from: Some("Selector".into()), to: Some("String".into()) },
+            package: None,
+            upsert: false,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg_declare).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn test_object_values() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+
+        let msg = ExecuteMsg::CreateDeeplink { deeplink: Deeplink { type_: "Query".into(), from: None, to: None }, package: None };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        let id: Uint64 = from_json(query(deps.as_ref(), mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
+
+        let msg = ExecuteMsg::SetValue { id, value: Value::Object("{\"limit\": 10,".to_string()) };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidObject { .. }));
+        let msg = ExecuteMsg::SetValue { id, value: Value::Object("[1, 2]".to_string()) };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidObject { .. }));
+
+        let object = r#"{
+            "where": {"type_id": {"_eq": 12}, "name": "caf\u00e9 \"x\""},
+            "order_by": [{"id": "asc"}, {"value": -1.5e3}],
+            "limit": 10
+        }"#;
+        let msg = ExecuteMsg::SetValue { id, value: Value::Object(object.to_string()) };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+
+        let value_at = |deps: Deps, path: &str| -> Option<String> {
+            let res: ValueAtPathResponse = from_json(query(deps, mock_env(), QueryMsg::ValueAtPath {
+                id,
+                path: path.to_string(),
+            }).unwrap()).unwrap();
+            res.value
+        };
+        assert_eq!(value_at(deps.as_ref(), "where.type_id"), Some("{\"_eq\": 12}".to_string()));
+        assert_eq!(value_at(deps.as_ref(), "where.type_id._eq"), Some("12".to_string()));
+        assert_eq!(value_at(deps.as_ref(), "where.name"), Some(r#""caf\u00e9 \"x\"""#.to_string()));
+        assert_eq!(value_at(deps.as_ref(), "order_by.1.value"), Some("-1.5e3".to_string()));
+        assert_eq!(value_at(deps.as_ref(), "order_by.2"), None);
+        assert_eq!(value_at(deps.as_ref(), "limit.value"), None);
+        assert_eq!(value_at(deps.as_ref(), "missing"), None);
+        assert_eq!(value_at(deps.as_ref(), ""), Some(object.to_string()));
+
+        // Size cap is configurable by admins
+        let msg = ExecuteMsg::UpdateMaxObjectSize { max_object_size: 16 };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), msg).unwrap();
+        let res: ConfigResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(res.max_object_size, 16);
+
+        let msg = ExecuteMsg::UpdateValue { id, value: Value::Object(object.to_string()) };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::ObjectTooLarge { id, size: object.len() as u64, max: 16 });
+        let msg = ExecuteMsg::UpdateValue { id, value: Value::Object("{\"limit\":5}".to_string()) };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        assert_eq!(value_at(deps.as_ref(), "limit"), Some("5".to_string()));
+    }
+
+    #[test]
+    fn test_package_namespaces() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+
+        let named = |name: &str, type_: &str, package: Option<&str>| ExecuteMsg::CreatedNamedDeeplink {
+            name: name.to_string(),
+            deeplink: Deeplink { type_: type_.into(), from: None, to: None },
+            package: package.map(|p| p.to_string()),
+            upsert: false,
+        };
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), named("Handler", "Type", Some("alice"))).unwrap_err();
+        assert_eq!(err, ContractError::PackageNotExists { package: "alice".to_string() });
+
+        let msg = ExecuteMsg::CreatePackage { name: "alice".to_string() };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg.clone()).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::PackageAlreadyExists { package: "alice".to_string() });
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), ExecuteMsg::CreatePackage { name: "bob".to_string() }).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), ExecuteMsg::CreatePackage { name: "bob/x".to_string() }).unwrap_err();
+        assert_eq!(err, ContractError::InvalidName { name: "bob/x".to_string() });
+
+        let res: PackageResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Package { name: "alice".to_string() }).unwrap()).unwrap();
+        assert_eq!(res.publisher, "alice");
+        let state: DeeplinkState = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Deeplink { id: res.id }).unwrap()).unwrap();
+        assert_eq!(state.type_, core_id("PackageNamespace"));
+
+        // Both packages define Handler, plain names go to the given package
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), named("Handler", "Type", Some("alice"))).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), named("bob/Handler", "Type", None)).unwrap();
+        let alice_handler: NameResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ResolveName { name: "alice/Handler".to_string() }).unwrap()).unwrap();
+        let bob_handler: NameResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ResolveName { name: "bob/Handler".to_string() }).unwrap()).unwrap();
+        assert_ne!(alice_handler.id, bob_handler.id);
+        assert_ne!(alice_handler.id, Uint64::new(core_id("Handler")));
+
+        // Names belong to the publisher, global names to admins
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), named("alice/Tree", "Type", None)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), named("Tree", "Type", None)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), named("alice/Tree", "Type", None)).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), named("alice/", "Type", None)).unwrap_err();
+        assert_eq!(err, ContractError::InvalidName { name: "alice/".to_string() });
+
+        // Default package is searched first, then global names
+        let msg = ExecuteMsg::CreateDeeplink {
+            deeplink: Deeplink { type_: "Handler".into(), from: None, to: None },
+            package: Some("bob".to_string()),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("carol", &[]), msg).unwrap();
+        let id: Uint64 = from_json(query(deps.as_ref(), mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
+        let state: DeeplinkState = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Deeplink { id }).unwrap()).unwrap();
+        assert_eq!(state.type_, bob_handler.id.u64());
+
+        let msg = ExecuteMsg::UpdateDeeplink {
+            id,
+            deeplink: Deeplink { type_: "alice/Handler".into(), from: None, to: None },
+            package: Some("bob".to_string()),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("carol", &[]), msg).unwrap();
+        let state: DeeplinkState = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Deeplink { id }).unwrap()).unwrap();
+        assert_eq!(state.type_, alice_handler.id.u64());
+
+        let msg = ExecuteMsg::UpdateDeeplink {
+            id,
+            deeplink: Deeplink { type_: "Selector".into(), from: None, to: None },
+            package: Some("bob".to_string()),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("carol", &[]), msg).unwrap();
+        let state: DeeplinkState = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Deeplink { id }).unwrap()).unwrap();
+        assert_eq!(state.type_, core_id("Selector"));
+    }
+
+    #[test]
+    fn test_install_package() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+
+        let entry = |id: &str, type_: &str, from: Option<&str>, to: Option<&str>| NamedDeeplink {
+            id: id.to_string(),
+            type_: type_.to_string(),
+            from: from.map(|f| f.to_string()),
+            to: to.map(|t| t.to_string()),
+            value: None,
+        };
+        let manifest = vec![
+            entry("Link", "Type", Some("Node"), Some("Node")),
+            entry("Node", "Type", None, None),
+            entry("edge", "Link", Some("a"), Some("b")),
+            entry("a", "Node", None, None),
+            entry("b", "Node", None, None),
+        ];
+        let install = |manifest: Vec<NamedDeeplink>| ExecuteMsg::InstallPackage {
+            package: "graph".to_string(),
+            version: "1.0.0".to_string(),
+            deeplinks: manifest,
+            dependencies: vec![],
+        };
+
+        // No entry is written when any of them is invalid, the namespace created
+        // before validation is reverted with the transaction on chain
+        let mut invalid = manifest.clone();
+        invalid.push(entry("loop", "Link", Some("edge"), Some("a")));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), install(invalid)).unwrap_err();
+        assert!(matches!(err, ContractError::TypeConflict { .. }));
+        let mut invalid = manifest.clone();
+        invalid.push(entry("c", "Missing", None, None));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), install(invalid)).unwrap_err();
+        assert_eq!(err, ContractError::TypeNotExists { type_: "Missing".to_string() });
+        let mut invalid = manifest.clone();
+        invalid.push(entry("a", "Node", None, None));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), install(invalid)).unwrap_err();
+        assert_eq!(err, ContractError::AlreadyExists { name: "graph/a".to_string() });
+        let msg = ExecuteMsg::InstallPackage { package: "graph".to_string(), version: "one".to_string(), deeplinks: manifest.clone(), dependencies: vec![] };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::SemVer(_)));
+        assert!(query(deps.as_ref(), mock_env(), QueryMsg::Package { name: "graph".to_string() }).is_err());
+        assert!(query(deps.as_ref(), mock_env(), QueryMsg::ResolveName { name: "graph/Node".to_string() }).is_err());
+
+        let last_id: Uint64 = from_json(query(deps.as_ref(), mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), install(manifest)).unwrap();
+        let data: InstallPackageResponse = from_json(res.data.unwrap()).unwrap();
+        assert_eq!(data.package, "graph");
+
+        // Referenced entries get lower ids, the package deeplink comes first
+        let first = last_id.u64() + 2;
+        assert_eq!(data.ids, [first + 3, first, first + 4, first + 1, first + 2].map(Uint64::new).to_vec());
+        let edge: DeeplinkState = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Deeplink { id: data.ids[2] }).unwrap()).unwrap();
+        assert_eq!((edge.type_, edge.from, edge.to), (first + 3, Some(first + 1), Some(first + 2)));
+        let res: NameResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ResolveName { name: "graph/Node".to_string() }).unwrap()).unwrap();
+        assert_eq!(res.id, Uint64::new(first));
+
+        let res: PackageResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Package { name: "graph".to_string() }).unwrap()).unwrap();
+        assert_eq!((res.id, res.publisher.as_str(), res.version), (Uint64::new(first - 1), "alice", Some("1.0.0".to_string())));
+        let res: PackageIdsResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::PackageIds {
+            package: "graph".to_string(),
+            start_after: None,
+            limit: None,
+        }).unwrap()).unwrap();
+        assert_eq!(res.ids, (first..first + 5).map(Uint64::new).collect::<Vec<_>>());
+
+        // Only the publisher installs into a package
+        let msg = ExecuteMsg::InstallPackage {
+            package: "graph".to_string(),
+            version: "1.1.0".to_string(),
+            deeplinks: vec![entry("c", "Node", None, None)],
+            dependencies: vec![],
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+
+        // Entries typed by each other are installed as well
+        let msg = ExecuteMsg::InstallPackage {
+            package: "cycle".to_string(),
+            version: "0.1.0".to_string(),
+            deeplinks: vec![entry("A", "B", None, None), entry("B", "A", None, None)],
+            dependencies: vec![],
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap();
+        let data: InstallPackageResponse = from_json(res.data.unwrap()).unwrap();
+        let state: DeeplinkState = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Deeplink { id: data.ids[0] }).unwrap()).unwrap();
+        assert_eq!(state.type_, data.ids[1].u64());
+    }
+
+    #[test]
+    fn test_export() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+
+        let entry = |id: &str, type_: &str, ends: Option<(&str, &str)>, value: Option<Value>| NamedDeeplink {
+            id: id.to_string(),
+            type_: type_.to_string(),
+            from: ends.map(|(from, _)| from.to_string()),
+            to: ends.map(|(_, to)| to.to_string()),
+            value: value.map(Into::into),
+        };
+        let manifest = vec![
+            entry("edge", "Link", Some(("a", "b")), None),
+            entry("Link", "Type", Some(("Node", "Node")), None),
+            entry("Node", "Type", None, None),
+            entry("nodeValue", "Value", Some(("Node", "String")), None),
+            entry("a", "Node", None, Some(Value::String("first".to_string()))),
+            entry("b", "Node", None, None),
+        ];
+        let msg = ExecuteMsg::InstallPackage { package: "graph".to_string(), version: "1.0.0".to_string(), deeplinks: manifest, dependencies: vec![] };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        let a: NameResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ResolveName { name: "graph/a".to_string() }).unwrap()).unwrap();
+        let res: ValueResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Value { id: a.id }).unwrap()).unwrap();
+        assert_eq!(res.value, Some(Value::String("first".to_string())));
+
+        // Exported in dependency order with local names and values
+        let export: ExportResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ExportPackage { package: "graph".to_string(), limit: None }).unwrap()).unwrap();
+        assert_eq!(export.version, Some("1.0.0".to_string()));
+        assert_eq!(export.deeplinks, vec![
+            entry("Node", "Type", None, None),
+            entry("nodeValue", "Value", Some(("Node", "String")), None),
+            entry("a", "Node", None, Some(Value::String("first".to_string()))),
+            entry("b", "Node", None, None),
+            entry("Link", "Type", Some(("Node", "Node")), None),
+            entry("edge", "Link", Some(("a", "b")), None),
+        ]);
+
+        // Re-imports cleanly
+        let msg = ExecuteMsg::InstallPackage { package: "copy".to_string(), version: "1.0.0".to_string(), deeplinks: export.deeplinks.clone(), dependencies: vec![] };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap();
+        let copy: ExportResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ExportPackage { package: "copy".to_string(), limit: None }).unwrap()).unwrap();
+        assert_eq!(copy.deeplinks, export.deeplinks);
+
+        // Subgraph through Contain links, unnamed deeplinks get symbolic names
+        let msg = ExecuteMsg::CreateDeeplinks {
+            deeplinks: vec![
+                Deeplink { type_: "Space".into(), from: None, to: None },
+                Deeplink { type_: "Query".into(), from: None, to: None },
+            ],
+            package: None,
+            continue_on_error: false,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("carol", &[]), msg).unwrap();
+        let query_id: Uint64 = from_json(query(deps.as_ref(), mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
+        let space_id = query_id - Uint64::one();
+        let msg = ExecuteMsg::CreateDeeplinks {
+            deeplinks: vec![
+                Deeplink { type_: "Contain".into(), from: Some(DeeplinkRef::Id(space_id)), to: Some(DeeplinkRef::Id(query_id)) },
+                Deeplink { type_: "Contain".into(), from: Some(DeeplinkRef::Id(query_id)), to: Some("graph/a".into()) },
+                Deeplink { type_: "Contain".into(), from: Some("graph/b".into()), to: Some(DeeplinkRef::Id(space_id)) },
+            ],
+            package: None,
+            continue_on_error: false,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("carol", &[]), msg).unwrap();
+        let export: ExportResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ExportSubgraph { root: DeeplinkRef::Id(space_id), limit: None }).unwrap()).unwrap();
+        let symbol = |id: Uint64| format!("_{}", id);
+        assert_eq!(export.deeplinks, vec![
+            entry("a", "graph/Node", None, Some(Value::String("first".to_string()))),
+            entry(&symbol(space_id), "Space", None, None),
+            entry(&symbol(query_id), "Query", None, None),
+            entry(&symbol(query_id + Uint64::one()), "Contain", Some((&symbol(space_id), &symbol(query_id))), None),
+            entry(&symbol(query_id + Uint64::new(2)), "Contain", Some((&symbol(query_id), "a")), None),
+        ]);
+        let err = query(deps.as_ref(), mock_env(), QueryMsg::ExportSubgraph { root: DeeplinkRef::Id(space_id), limit: Some(4) }).unwrap_err();
+        assert_eq!(err, StdError::generic_err("Export has more than 4 deeplinks"));
+
+        // Symbolic names stay local to the manifest on re-import
+        let msg = ExecuteMsg::InstallPackage { package: "space".to_string(), version: "0.1.0".to_string(), deeplinks: export.deeplinks, dependencies: vec![] };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("carol", &[]), msg).unwrap();
+        let data: InstallPackageResponse = from_json(res.data.unwrap()).unwrap();
+        let res: NameResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ResolveName { name: "space/a".to_string() }).unwrap()).unwrap();
+        assert_eq!(res.id, data.ids[0]);
+        assert!(query(deps.as_ref(), mock_env(), QueryMsg::ResolveName { name: format!("space/{}", symbol(space_id)) }).is_err());
+        let res: NamesResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Names { id: data.ids[1] }).unwrap()).unwrap();
+        assert!(res.names.is_empty());
+
+        // Local symbols don't take the names of outside references, deleted targets are left out
+        let msg = ExecuteMsg::InstallPackage { package: "shadow".to_string(), version: "0.1.0".to_string(), deeplinks: vec![entry("Contain", "Type", None, None)], dependencies: vec![] };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("carol", &[]), msg).unwrap();
+        let shadow = from_json::<InstallPackageResponse>(res.data.unwrap()).unwrap().ids[0];
+        let msg = ExecuteMsg::CreateDeeplink { deeplink: Deeplink { type_: "Space".into(), from: None, to: None }, package: None };
+        execute(deps.as_mut(), mock_env(), mock_info("carol", &[]), msg).unwrap();
+        let root: Uint64 = from_json(query(deps.as_ref(), mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
+        let msg = ExecuteMsg::CreateDeeplinks {
+            deeplinks: vec![
+                Deeplink { type_: "Contain".into(), from: Some(DeeplinkRef::Id(root)), to: Some(DeeplinkRef::Id(shadow)) },
+                Deeplink { type_: "Contain".into(), from: Some(DeeplinkRef::Id(root)), to: Some(DeeplinkRef::Id(query_id)) },
+            ],
+            package: None,
+            continue_on_error: false,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("carol", &[]), msg).unwrap();
+        DELETED_IDS.save(&mut deps.storage, query_id.u64(), &true).unwrap();
+        let export: ExportResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ExportSubgraph { root: DeeplinkRef::Id(root), limit: None }).unwrap()).unwrap();
+        assert_eq!(export.deeplinks, vec![
+            entry(&symbol(shadow), "Type", None, None),
+            entry(&symbol(root), "Space", None, None),
+            entry(&symbol(root + Uint64::one()), "Contain", Some((&symbol(root), &symbol(shadow))), None),
+        ]);
+        let msg = ExecuteMsg::InstallPackage { package: "unshadowed".to_string(), version: "0.1.0".to_string(), deeplinks: export.deeplinks, dependencies: vec![] };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("carol", &[]), msg).unwrap();
+        let data: InstallPackageResponse = from_json(res.data.unwrap()).unwrap();
+        let state: DeeplinkState = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Deeplink { id: data.ids[2] }).unwrap()).unwrap();
+        assert_eq!(state.type_, core_id("Contain"));
+
+        // Values in the format of core.json, objects as JSON text
+        let manifest: Vec<NamedDeeplink> = from_json(br#"[
+            {"id": "portA", "type": "Port", "value": {"value": 8080}},
+            {"id": "queryA", "type": "Query", "value": {"value": "{\"limit\": 1}"}},
+            {"id": "Router", "type": "Type", "value": {"value": "Router"}}
+        ]"#).unwrap();
+        let msg = ExecuteMsg::InstallPackage { package: "values".to_string(), version: "0.1.0".to_string(), deeplinks: manifest, dependencies: vec![] };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("carol", &[]), msg).unwrap();
+        let data: InstallPackageResponse = from_json(res.data.unwrap()).unwrap();
+        let values = data.ids.into_iter()
+            .map(|id| from_json::<ValueResponse>(query(deps.as_ref(), mock_env(), QueryMsg::Value { id }).unwrap()).unwrap().value.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![
+            Value::Number(Uint64::new(8080)),
+            Value::Object("{\"limit\": 1}".to_string()),
+            Value::String("Router".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_package_versions() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+
+        let entry = |id: &str, type_: &str| NamedDeeplink {
+            id: id.to_string(),
+            type_: type_.to_string(),
+            from: None,
+            to: None,
+            value: None,
+        };
+        let install = |package: &str, version: &str, deeplinks: Vec<NamedDeeplink>, dependencies: Vec<(&str, &str)>| ExecuteMsg::InstallPackage {
+            package: package.to_string(),
+            version: version.to_string(),
+            deeplinks,
+            dependencies: dependencies.into_iter()
+                .map(|(package, version)| PackageDependency { package: package.to_string(), version: version.to_string() })
+                .collect(),
+        };
+        let resolve = |deps: Deps, name: &str| query(deps, mock_env(), QueryMsg::ResolveName { name: name.to_string() })
+            .ok()
+            .map(|res| from_json::<NameResponse>(res).unwrap().id);
+
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), install("base", "1.0.0", vec![entry("Node", "Type")], vec![])).unwrap();
+        let node_v1 = resolve(deps.as_ref(), "base/Node").unwrap();
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), install("app", "1.0.0", vec![], vec![("base", "^2")])).unwrap_err();
+        assert_eq!(err, ContractError::DependencyIncompatible {
+            package: "base".to_string(),
+            required: "^2".to_string(),
+            version: "1.0.0".to_string(),
+        });
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), install("app", "1.0.0", vec![], vec![("missing", "*")])).unwrap_err();
+        assert_eq!(err, ContractError::DependencyMissing { package: "missing".to_string(), required: "*".to_string() });
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), install("app", "1.0.0", vec![], vec![("base", "one")])).unwrap_err();
+        assert!(matches!(err, ContractError::SemVer(_)));
+        let msg = install("app", "1.0.0", vec![entry("item", "base/Node")], vec![("base", "^1")]);
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap();
+
+        // Dependents keep the base within their range
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), install("base", "2.0.0", vec![entry("Node", "Type")], vec![])).unwrap_err();
+        assert_eq!(err, ContractError::DependencyIncompatible {
+            package: "base".to_string(),
+            required: "^1".to_string(),
+            version: "2.0.0".to_string(),
+        });
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), install("base", "1.0.0", vec![], vec![])).unwrap_err();
+        assert_eq!(err, ContractError::PackageVersionExists { package: "base".to_string(), version: "1.0.0".to_string() });
+
+        // New version takes over the names
+        let msg = install("base", "1.1.0", vec![entry("Node", "Type"), entry("Edge", "Type")], vec![]);
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        let node_v2 = resolve(deps.as_ref(), "base/Node").unwrap();
+        assert_ne!(node_v1, node_v2);
+        assert!(resolve(deps.as_ref(), "base/Edge").is_some());
+        let res: NamesResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Names { id: node_v1 }).unwrap()).unwrap();
+        assert!(res.names.is_empty());
+
+        let res: PackageVersionsResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::PackageVersions { package: "base".to_string() }).unwrap()).unwrap();
+        assert_eq!(res.active, Some("1.1.0".to_string()));
+        assert_eq!(res.versions.iter().map(|v| v.version.as_str()).collect::<Vec<_>>(), vec!["1.0.0", "1.1.0"]);
+        let package: PackageResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Package { name: "base".to_string() }).unwrap()).unwrap();
+        let active: DeeplinksResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ByTypeFrom {
+            type_: "PackageActive".into(),
+            from: DeeplinkRef::Id(package.id),
+            start_after: None,
+            limit: None,
+        }).unwrap()).unwrap();
+        assert_eq!(active.deeplinks.len(), 1);
+        assert_eq!(active.deeplinks[0].deeplink.to, Some(res.versions[1].id.u64()));
+        let versions: DeeplinksResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ByTypeFrom {
+            type_: "PackageVersion".into(),
+            from: DeeplinkRef::Id(package.id),
+            start_after: None,
+            limit: None,
+        }).unwrap()).unwrap();
+        let res: ValueResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Value { id: versions.deeplinks[1].id }).unwrap()).unwrap();
+        assert_eq!(res.value, Some(Value::String("1.1.0".to_string())));
+
+        // Switching back restores the names of the version
+        let msg = ExecuteMsg::ActivatePackageVersion { package: "base".to_string(), version: "1.0.0".to_string() };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        assert_eq!(resolve(deps.as_ref(), "base/Node"), Some(node_v1));
+        assert_eq!(resolve(deps.as_ref(), "base/Edge"), None);
+        let export: ExportResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ExportPackage { package: "base".to_string(), limit: None }).unwrap()).unwrap();
+        assert_eq!((export.version, export.deeplinks), (Some("1.0.0".to_string()), vec![entry("Node", "Type")]));
+        let msg = ExecuteMsg::ActivatePackageVersion { package: "base".to_string(), version: "3.0.0".to_string() };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::PackageVersionNotExists { package: "base".to_string(), version: "3.0.0".to_string() });
+    }
+
+    #[test]
+    fn test_uninstall_package() {
+        let mut deps = mock_dependencies();
+        instantiate_with_core(deps.as_mut());
+
+        let entry = |id: &str, type_: &str| NamedDeeplink {
+            id: id.to_string(),
+            type_: type_.to_string(),
+            from: None,
+            to: None,
+            value: None,
+        };
+        let install = |package: &str, deeplinks: Vec<NamedDeeplink>, dependencies: Vec<PackageDependency>| ExecuteMsg::InstallPackage {
+            package: package.to_string(),
+            version: "1.0.0".to_string(),
+            deeplinks,
+            dependencies,
+        };
+        let uninstall = |package: &str, mode: Option<DeleteMode>| ExecuteMsg::UninstallPackage { package: package.to_string(), mode };
+        let resolve = |deps: Deps, name: &str| query(deps, mock_env(), QueryMsg::ResolveName { name: name.to_string() })
+            .ok()
+            .map(|res| from_json::<NameResponse>(res).unwrap().id);
+
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), install("base", vec![entry("Node", "Type")], vec![])).unwrap();
+        let node = resolve(deps.as_ref(), "base/Node").unwrap();
+        let dependency = PackageDependency { package: "base".to_string(), version: "^1".to_string() };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), install("app", vec![entry("item", "base/Node")], vec![dependency])).unwrap();
+        let item = resolve(deps.as_ref(), "app/item").unwrap();
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), uninstall("base", None)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), uninstall("base", None)).unwrap_err();
+        assert_eq!(err, ContractError::PackageHasDependents { package: "base".to_string(), dependents: vec!["app".to_string()] });
+
+        // User data of another creator blocks the uninstall, the cascade needs permission on it
+        let msg = ExecuteMsg::CreateDeeplink {
+            deeplink: Deeplink { type_: "app/item".into(), from: None, to: None },
+            package: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("carol", &[]), msg).unwrap();
+        let user_id: Uint64 = from_json(query(deps.as_ref(), mock_env(), QueryMsg::LastId {}).unwrap()).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), uninstall("app", None)).unwrap_err();
+        assert_eq!(err, ContractError::PackageHasReferrers { package: "app".to_string(), referrers: vec![user_id] });
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), uninstall("app", Some(DeleteMode::Cascade))).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // Forced uninstall of the base takes the dependent package and its data along
+        let res = execute(deps.as_mut(), mock_env(), mock_info("admin1", &[]), uninstall("base", Some(DeleteMode::Cascade))).unwrap();
+        assert_eq!(res.attributes[0].value, "uninstall_package");
+        let res: DeletedIdsResponse = from_json(query(deps.as_ref(), mock_env(), QueryMsg::ListDeleted { start_after: None, limit: None }).unwrap()).unwrap();
+        assert!([node, item, user_id].iter().all(|id| res.ids.contains(id)));
+        assert_eq!(resolve(deps.as_ref(), "base/Node"), None);
+        assert!(query(deps.as_ref(), mock_env(), QueryMsg::Package { name: "base".to_string() }).is_err());
+
+        // The dependent package is still registered and can be removed, the base installs again
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), uninstall("app", None)).unwrap();
+        assert_eq!(resolve(deps.as_ref(), "app/item"), None);
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), install("base", vec![entry("Node", "Type")], vec![])).unwrap();
+        assert_ne!(resolve(deps.as_ref(), "base/Node"), Some(node));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), uninstall("missing", None)).unwrap_err();
+        assert_eq!(err, ContractError::PackageNotExists { package: "missing".to_string() });
     }
 
     #[test]
@@ -153,7 +2035,7 @@ mod tests {
         let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
         assert_eq!(res.attributes[0].value, "update_admins");
 
-        let config: Config = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        let config: Config = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
         assert_eq!(config.admins, vec![Addr::unchecked("admin3"), Addr::unchecked("admin4")]);
     }
 
@@ -172,7 +2054,7 @@ mod tests {
         let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
         assert_eq!(res.attributes[0].value, "update_executors");
 
-        let config: Config = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        let config: Config = from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
         assert_eq!(config.executors, vec![Addr::unchecked("exec3"), Addr::unchecked("exec4")]);
     }
 }